@@ -6,9 +6,19 @@ fn generate_protobuf_binding_file() {
         out_dir: "proto",
         input: &["proto/proto_model.proto"],
         includes: &["proto"],
-        ..Default::default()
+        customize: protobuf_codegen_pure::Customize {
+            serde_derive: Some(true),
+            ..Default::default()
+        },
     })
     .unwrap();
+
+    // The generated serde attributes are guarded by a `with-serde` feature,
+    // the feature of this crate is named `serde`.
+    let path = "proto/proto_model.rs";
+    let code = std::fs::read_to_string(path).unwrap();
+    let code = code.replace("feature = \"with-serde\"", "feature = \"serde\"");
+    std::fs::write(path, code).unwrap();
 }
 
 #[cfg(not(feature = "gen"))]
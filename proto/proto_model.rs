@@ -22,12 +22,15 @@ use protobuf::Message as Message_imported_for_functions;
 use protobuf::ProtobufEnum as ProtobufEnum_imported_for_functions;
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct LabelPair {
     // message fields
     name: ::protobuf::SingularField<::std::string::String>,
     value: ::protobuf::SingularField<::std::string::String>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -247,11 +250,14 @@ impl ::protobuf::reflect::ProtobufValue for LabelPair {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Gauge {
     // message fields
     value: ::std::option::Option<f64>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -407,11 +413,15 @@ impl ::protobuf::reflect::ProtobufValue for Gauge {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Counter {
     // message fields
     value: ::std::option::Option<f64>,
+    created_timestamp: ::protobuf::SingularPtrField<::protobuf::well_known_types::Timestamp>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -438,10 +448,48 @@ impl Counter {
     pub fn get_value(&self) -> f64 {
         self.value.unwrap_or(0.)
     }
+
+    // optional .google.protobuf.Timestamp created_timestamp = 3;
+
+    pub fn clear_created_timestamp(&mut self) {
+        self.created_timestamp.clear();
+    }
+
+    pub fn has_created_timestamp(&self) -> bool {
+        self.created_timestamp.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_created_timestamp(&mut self, v: ::protobuf::well_known_types::Timestamp) {
+        self.created_timestamp = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_created_timestamp(&mut self) -> &mut ::protobuf::well_known_types::Timestamp {
+        if self.created_timestamp.is_none() {
+            self.created_timestamp.set_default();
+        }
+        self.created_timestamp.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_created_timestamp(&mut self) -> ::protobuf::well_known_types::Timestamp {
+        self.created_timestamp.take().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::new())
+    }
+
+    pub fn get_created_timestamp(&self) -> &::protobuf::well_known_types::Timestamp {
+        self.created_timestamp.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::default_instance())
+    }
 }
 
 impl ::protobuf::Message for Counter {
     fn is_initialized(&self) -> bool {
+        for v in &self.created_timestamp {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -456,6 +504,9 @@ impl ::protobuf::Message for Counter {
                     let tmp = is.read_double()?;
                     self.value = ::std::option::Option::Some(tmp);
                 },
+                3 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.created_timestamp)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -471,6 +522,10 @@ impl ::protobuf::Message for Counter {
         if let Some(v) = self.value {
             my_size += 9;
         }
+        if let Some(ref v) = self.created_timestamp.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -480,6 +535,11 @@ impl ::protobuf::Message for Counter {
         if let Some(v) = self.value {
             os.write_double(1, v)?;
         }
+        if let Some(ref v) = self.created_timestamp.as_ref() {
+            os.write_tag(3, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -527,6 +587,11 @@ impl ::protobuf::Message for Counter {
                     |m: &Counter| { &m.value },
                     |m: &mut Counter| { &mut m.value },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::Timestamp>>(
+                    "created_timestamp",
+                    |m: &Counter| { &m.created_timestamp },
+                    |m: &mut Counter| { &mut m.created_timestamp },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<Counter>(
                     "Counter",
                     fields,
@@ -550,6 +615,7 @@ impl ::protobuf::Message for Counter {
 impl ::protobuf::Clear for Counter {
     fn clear(&mut self) {
         self.clear_value();
+        self.clear_created_timestamp();
         self.unknown_fields.clear();
     }
 }
@@ -567,12 +633,15 @@ impl ::protobuf::reflect::ProtobufValue for Counter {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Quantile {
     // message fields
     quantile: ::std::option::Option<f64>,
     value: ::std::option::Option<f64>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -766,13 +835,17 @@ impl ::protobuf::reflect::ProtobufValue for Quantile {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Summary {
     // message fields
     sample_count: ::std::option::Option<u64>,
     sample_sum: ::std::option::Option<f64>,
     quantile: ::protobuf::RepeatedField<Quantile>,
+    created_timestamp: ::protobuf::SingularPtrField<::protobuf::well_known_types::Timestamp>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -843,6 +916,39 @@ impl Summary {
     pub fn get_quantile(&self) -> &[Quantile] {
         &self.quantile
     }
+
+    // optional .google.protobuf.Timestamp created_timestamp = 4;
+
+    pub fn clear_created_timestamp(&mut self) {
+        self.created_timestamp.clear();
+    }
+
+    pub fn has_created_timestamp(&self) -> bool {
+        self.created_timestamp.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_created_timestamp(&mut self, v: ::protobuf::well_known_types::Timestamp) {
+        self.created_timestamp = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_created_timestamp(&mut self) -> &mut ::protobuf::well_known_types::Timestamp {
+        if self.created_timestamp.is_none() {
+            self.created_timestamp.set_default();
+        }
+        self.created_timestamp.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_created_timestamp(&mut self) -> ::protobuf::well_known_types::Timestamp {
+        self.created_timestamp.take().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::new())
+    }
+
+    pub fn get_created_timestamp(&self) -> &::protobuf::well_known_types::Timestamp {
+        self.created_timestamp.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::default_instance())
+    }
 }
 
 impl ::protobuf::Message for Summary {
@@ -852,6 +958,11 @@ impl ::protobuf::Message for Summary {
                 return false;
             }
         };
+        for v in &self.created_timestamp {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -876,6 +987,9 @@ impl ::protobuf::Message for Summary {
                 3 => {
                     ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.quantile)?;
                 },
+                4 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.created_timestamp)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -898,6 +1012,10 @@ impl ::protobuf::Message for Summary {
             let len = value.compute_size();
             my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         };
+        if let Some(ref v) = self.created_timestamp.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -915,6 +1033,11 @@ impl ::protobuf::Message for Summary {
             os.write_raw_varint32(v.get_cached_size())?;
             v.write_to_with_cached_sizes(os)?;
         };
+        if let Some(ref v) = self.created_timestamp.as_ref() {
+            os.write_tag(4, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -972,6 +1095,11 @@ impl ::protobuf::Message for Summary {
                     |m: &Summary| { &m.quantile },
                     |m: &mut Summary| { &mut m.quantile },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::Timestamp>>(
+                    "created_timestamp",
+                    |m: &Summary| { &m.created_timestamp },
+                    |m: &mut Summary| { &mut m.created_timestamp },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<Summary>(
                     "Summary",
                     fields,
@@ -997,6 +1125,7 @@ impl ::protobuf::Clear for Summary {
         self.clear_sample_count();
         self.clear_sample_sum();
         self.clear_quantile();
+        self.clear_created_timestamp();
         self.unknown_fields.clear();
     }
 }
@@ -1014,11 +1143,14 @@ impl ::protobuf::reflect::ProtobufValue for Summary {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Untyped {
     // message fields
     value: ::std::option::Option<f64>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -1174,13 +1306,17 @@ impl ::protobuf::reflect::ProtobufValue for Untyped {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Histogram {
     // message fields
     sample_count: ::std::option::Option<u64>,
     sample_sum: ::std::option::Option<f64>,
     bucket: ::protobuf::RepeatedField<Bucket>,
+    created_timestamp: ::protobuf::SingularPtrField<::protobuf::well_known_types::Timestamp>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -1251,6 +1387,39 @@ impl Histogram {
     pub fn get_bucket(&self) -> &[Bucket] {
         &self.bucket
     }
+
+    // optional .google.protobuf.Timestamp created_timestamp = 15;
+
+    pub fn clear_created_timestamp(&mut self) {
+        self.created_timestamp.clear();
+    }
+
+    pub fn has_created_timestamp(&self) -> bool {
+        self.created_timestamp.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_created_timestamp(&mut self, v: ::protobuf::well_known_types::Timestamp) {
+        self.created_timestamp = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_created_timestamp(&mut self) -> &mut ::protobuf::well_known_types::Timestamp {
+        if self.created_timestamp.is_none() {
+            self.created_timestamp.set_default();
+        }
+        self.created_timestamp.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_created_timestamp(&mut self) -> ::protobuf::well_known_types::Timestamp {
+        self.created_timestamp.take().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::new())
+    }
+
+    pub fn get_created_timestamp(&self) -> &::protobuf::well_known_types::Timestamp {
+        self.created_timestamp.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::default_instance())
+    }
 }
 
 impl ::protobuf::Message for Histogram {
@@ -1260,6 +1429,11 @@ impl ::protobuf::Message for Histogram {
                 return false;
             }
         };
+        for v in &self.created_timestamp {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
         true
     }
 
@@ -1284,6 +1458,9 @@ impl ::protobuf::Message for Histogram {
                 3 => {
                     ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.bucket)?;
                 },
+                15 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.created_timestamp)?;
+                },
                 _ => {
                     ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
                 },
@@ -1306,6 +1483,10 @@ impl ::protobuf::Message for Histogram {
             let len = value.compute_size();
             my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
         };
+        if let Some(ref v) = self.created_timestamp.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
         self.cached_size.set(my_size);
         my_size
@@ -1323,6 +1504,11 @@ impl ::protobuf::Message for Histogram {
             os.write_raw_varint32(v.get_cached_size())?;
             v.write_to_with_cached_sizes(os)?;
         };
+        if let Some(ref v) = self.created_timestamp.as_ref() {
+            os.write_tag(15, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
         os.write_unknown_fields(self.get_unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -1380,6 +1566,11 @@ impl ::protobuf::Message for Histogram {
                     |m: &Histogram| { &m.bucket },
                     |m: &mut Histogram| { &mut m.bucket },
                 ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::Timestamp>>(
+                    "created_timestamp",
+                    |m: &Histogram| { &m.created_timestamp },
+                    |m: &mut Histogram| { &mut m.created_timestamp },
+                ));
                 ::protobuf::reflect::MessageDescriptor::new::<Histogram>(
                     "Histogram",
                     fields,
@@ -1405,6 +1596,7 @@ impl ::protobuf::Clear for Histogram {
         self.clear_sample_count();
         self.clear_sample_sum();
         self.clear_bucket();
+        self.clear_created_timestamp();
         self.unknown_fields.clear();
     }
 }
@@ -1422,12 +1614,15 @@ impl ::protobuf::reflect::ProtobufValue for Histogram {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Bucket {
     // message fields
     cumulative_count: ::std::option::Option<u64>,
     upper_bound: ::std::option::Option<f64>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -1621,6 +1816,7 @@ impl ::protobuf::reflect::ProtobufValue for Bucket {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Metric {
     // message fields
     label: ::protobuf::RepeatedField<LabelPair>,
@@ -1631,7 +1827,9 @@ pub struct Metric {
     histogram: ::protobuf::SingularPtrField<Histogram>,
     timestamp_ms: ::std::option::Option<i64>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -2115,6 +2313,7 @@ impl ::protobuf::reflect::ProtobufValue for Metric {
 }
 
 #[derive(PartialEq,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct MetricFamily {
     // message fields
     name: ::protobuf::SingularField<::std::string::String>,
@@ -2122,7 +2321,9 @@ pub struct MetricFamily {
     field_type: ::std::option::Option<MetricType>,
     metric: ::protobuf::RepeatedField<Metric>,
     // special fields
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub unknown_fields: ::protobuf::UnknownFields,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub cached_size: ::protobuf::CachedSize,
 }
 
@@ -2424,12 +2625,14 @@ impl ::protobuf::reflect::ProtobufValue for MetricFamily {
 }
 
 #[derive(Clone,PartialEq,Eq,Debug,Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub enum MetricType {
     COUNTER = 0,
     GAUGE = 1,
     SUMMARY = 2,
     UNTYPED = 3,
     HISTOGRAM = 4,
+    GAUGE_HISTOGRAM = 5,
 }
 
 impl ::protobuf::ProtobufEnum for MetricType {
@@ -2444,6 +2647,7 @@ impl ::protobuf::ProtobufEnum for MetricType {
             2 => ::std::option::Option::Some(MetricType::SUMMARY),
             3 => ::std::option::Option::Some(MetricType::UNTYPED),
             4 => ::std::option::Option::Some(MetricType::HISTOGRAM),
+            5 => ::std::option::Option::Some(MetricType::GAUGE_HISTOGRAM),
             _ => ::std::option::Option::None
         }
     }
@@ -2455,6 +2659,7 @@ impl ::protobuf::ProtobufEnum for MetricType {
             MetricType::SUMMARY,
             MetricType::UNTYPED,
             MetricType::HISTOGRAM,
+            MetricType::GAUGE_HISTOGRAM,
         ];
         values
     }
@@ -2482,36 +2687,41 @@ impl ::protobuf::reflect::ProtobufValue for MetricType {
 }
 
 static file_descriptor_proto_data: &'static [u8] = b"\
-    \n\x11proto_model.proto\x12\x14io.prometheus.client\"0\n\tLabelPair\x12\
-    \x10\n\x04name\x18\x01\x20\x01(\tB\x02\x18\0\x12\x11\n\x05value\x18\x02\
-    \x20\x01(\tB\x02\x18\0\"\x1a\n\x05Gauge\x12\x11\n\x05value\x18\x01\x20\
-    \x01(\x01B\x02\x18\0\"\x1c\n\x07Counter\x12\x11\n\x05value\x18\x01\x20\
-    \x01(\x01B\x02\x18\0\"3\n\x08Quantile\x12\x14\n\x08quantile\x18\x01\x20\
-    \x01(\x01B\x02\x18\0\x12\x11\n\x05value\x18\x02\x20\x01(\x01B\x02\x18\0\
-    \"q\n\x07Summary\x12\x18\n\x0csample_count\x18\x01\x20\x01(\x04B\x02\x18\
-    \0\x12\x16\n\nsample_sum\x18\x02\x20\x01(\x01B\x02\x18\0\x124\n\x08quant\
-    ile\x18\x03\x20\x03(\x0b2\x1e.io.prometheus.client.QuantileB\x02\x18\0\"\
-    \x1c\n\x07Untyped\x12\x11\n\x05value\x18\x01\x20\x01(\x01B\x02\x18\0\"o\
-    \n\tHistogram\x12\x18\n\x0csample_count\x18\x01\x20\x01(\x04B\x02\x18\0\
-    \x12\x16\n\nsample_sum\x18\x02\x20\x01(\x01B\x02\x18\0\x120\n\x06bucket\
-    \x18\x03\x20\x03(\x0b2\x1c.io.prometheus.client.BucketB\x02\x18\0\"?\n\
-    \x06Bucket\x12\x1c\n\x10cumulative_count\x18\x01\x20\x01(\x04B\x02\x18\0\
-    \x12\x17\n\x0bupper_bound\x18\x02\x20\x01(\x01B\x02\x18\0\"\xda\x02\n\
-    \x06Metric\x122\n\x05label\x18\x01\x20\x03(\x0b2\x1f.io.prometheus.clien\
-    t.LabelPairB\x02\x18\0\x12.\n\x05gauge\x18\x02\x20\x01(\x0b2\x1b.io.prom\
-    etheus.client.GaugeB\x02\x18\0\x122\n\x07counter\x18\x03\x20\x01(\x0b2\
-    \x1d.io.prometheus.client.CounterB\x02\x18\0\x122\n\x07summary\x18\x04\
-    \x20\x01(\x0b2\x1d.io.prometheus.client.SummaryB\x02\x18\0\x122\n\x07unt\
-    yped\x18\x05\x20\x01(\x0b2\x1d.io.prometheus.client.UntypedB\x02\x18\0\
-    \x126\n\thistogram\x18\x07\x20\x01(\x0b2\x1f.io.prometheus.client.Histog\
-    ramB\x02\x18\0\x12\x18\n\x0ctimestamp_ms\x18\x06\x20\x01(\x03B\x02\x18\0\
-    \"\x98\x01\n\x0cMetricFamily\x12\x10\n\x04name\x18\x01\x20\x01(\tB\x02\
-    \x18\0\x12\x10\n\x04help\x18\x02\x20\x01(\tB\x02\x18\0\x122\n\x04type\
-    \x18\x03\x20\x01(\x0e2\x20.io.prometheus.client.MetricTypeB\x02\x18\0\
-    \x120\n\x06metric\x18\x04\x20\x03(\x0b2\x1c.io.prometheus.client.MetricB\
-    \x02\x18\0*Q\n\nMetricType\x12\x0b\n\x07COUNTER\x10\0\x12\t\n\x05GAUGE\
-    \x10\x01\x12\x0b\n\x07SUMMARY\x10\x02\x12\x0b\n\x07UNTYPED\x10\x03\x12\r\
-    \n\tHISTOGRAM\x10\x04\x1a\x02\x10\0B\0b\x06proto2\
+    \n\x11proto_model.proto\x12\x14io.prometheus.client\x1a\x1fgoogle/prot\
+    obuf/timestamp.proto\"0\n\tLabelPair\x12\x10\n\x04name\x18\x01\x20\x01\
+    (\tB\x02\x18\0\x12\x11\n\x05value\x18\x02\x20\x01(\tB\x02\x18\0\"\x1a\n\
+    \x05Gauge\x12\x11\n\x05value\x18\x01\x20\x01(\x01B\x02\x18\0\"W\n\x07C\
+    ounter\x12\x11\n\x05value\x18\x01\x20\x01(\x01B\x02\x18\0\x129\n\x11cr\
+    eated_timestamp\x18\x03\x20\x01(\x0b2\x1a.google.protobuf.TimestampB\x02\
+    \x18\0\"3\n\x08Quantile\x12\x14\n\x08quantile\x18\x01\x20\x01(\x01B\x02\
+    \x18\0\x12\x11\n\x05value\x18\x02\x20\x01(\x01B\x02\x18\0\"\xac\x01\n\x07\
+    Summary\x12\x18\n\x0csample_count\x18\x01\x20\x01(\x04B\x02\x18\0\x12\x16\
+    \n\nsample_sum\x18\x02\x20\x01(\x01B\x02\x18\0\x124\n\x08quantile\x18\x03\
+    \x20\x03(\x0b2\x1e.io.prometheus.client.QuantileB\x02\x18\0\x129\n\x11\
+    created_timestamp\x18\x04\x20\x01(\x0b2\x1a.google.protobuf.TimestampB\
+    \x02\x18\0\"\x1c\n\x07Untyped\x12\x11\n\x05value\x18\x01\x20\x01(\x01B\
+    \x02\x18\0\"\xaa\x01\n\tHistogram\x12\x18\n\x0csample_count\x18\x01\x20\
+    \x01(\x04B\x02\x18\0\x12\x16\n\nsample_sum\x18\x02\x20\x01(\x01B\x02\x18\
+    \0\x120\n\x06bucket\x18\x03\x20\x03(\x0b2\x1c.io.prometheus.client.Buc\
+    ketB\x02\x18\0\x129\n\x11created_timestamp\x18\x0f\x20\x01(\x0b2\x1a.g\
+    oogle.protobuf.TimestampB\x02\x18\0\"?\n\x06Bucket\x12\x1c\n\x10cumula\
+    tive_count\x18\x01\x20\x01(\x04B\x02\x18\0\x12\x17\n\x0bupper_bound\x18\
+    \x02\x20\x01(\x01B\x02\x18\0\"\xda\x02\n\x06Metric\x122\n\x05label\x18\
+    \x01\x20\x03(\x0b2\x1f.io.prometheus.client.LabelPairB\x02\x18\0\x12.\n\
+    \x05gauge\x18\x02\x20\x01(\x0b2\x1b.io.prometheus.client.GaugeB\x02\x18\
+    \0\x122\n\x07counter\x18\x03\x20\x01(\x0b2\x1d.io.prometheus.client.Co\
+    unterB\x02\x18\0\x122\n\x07summary\x18\x04\x20\x01(\x0b2\x1d.io.promet\
+    heus.client.SummaryB\x02\x18\0\x122\n\x07untyped\x18\x05\x20\x01(\x0b2\
+    \x1d.io.prometheus.client.UntypedB\x02\x18\0\x126\n\thistogram\x18\x07\
+    \x20\x01(\x0b2\x1f.io.prometheus.client.HistogramB\x02\x18\0\x12\x18\n\
+    \x0ctimestamp_ms\x18\x06\x20\x01(\x03B\x02\x18\0\"\x98\x01\n\x0cMetric\
+    Family\x12\x10\n\x04name\x18\x01\x20\x01(\tB\x02\x18\0\x12\x10\n\x04he\
+    lp\x18\x02\x20\x01(\tB\x02\x18\0\x122\n\x04type\x18\x03\x20\x01(\x0e2\x20\
+    .io.prometheus.client.MetricTypeB\x02\x18\0\x120\n\x06metric\x18\x04\x20\
+    \x03(\x0b2\x1c.io.prometheus.client.MetricB\x02\x18\0*f\n\nMetricType\x12\
+    \x0b\n\x07COUNTER\x10\0\x12\t\n\x05GAUGE\x10\x01\x12\x0b\n\x07SUMMARY\x10\
+    \x02\x12\x0b\n\x07UNTYPED\x10\x03\x12\r\n\tHISTOGRAM\x10\x04\x12\x13\n\
+    \x0fGAUGE_HISTOGRAM\x10\x05\x1a\x02\x10\0B\0b\x06proto2\
 ";
 
 static mut file_descriptor_proto_lazy: ::protobuf::lazy::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::lazy::Lazy {
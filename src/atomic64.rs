@@ -15,6 +15,8 @@ pub trait Number:
     fn from_i64(v: i64) -> Self;
     /// Convert to a f64.
     fn into_f64(self) -> f64;
+    /// Convert from a f64, truncating the fractional part for integers.
+    fn from_f64(v: f64) -> Self;
 }
 
 impl Number for i64 {
@@ -27,6 +29,11 @@ impl Number for i64 {
     fn into_f64(self) -> f64 {
         self as f64
     }
+
+    #[inline]
+    fn from_f64(v: f64) -> Self {
+        v as i64
+    }
 }
 
 impl Number for u64 {
@@ -39,6 +46,11 @@ impl Number for u64 {
     fn into_f64(self) -> f64 {
         self as f64
     }
+
+    #[inline]
+    fn from_f64(v: f64) -> Self {
+        v as u64
+    }
 }
 
 impl Number for f64 {
@@ -51,6 +63,11 @@ impl Number for f64 {
     fn into_f64(self) -> f64 {
         self
     }
+
+    #[inline]
+    fn from_f64(v: f64) -> Self {
+        v
+    }
 }
 
 /// An interface for atomics. Used to generically model float metrics and integer metrics, i.e.
@@ -68,6 +85,12 @@ pub trait Atomic: Send + Sync {
     fn inc_by(&self, delta: Self::T);
     /// Decrement the value by a given amount.
     fn dec_by(&self, delta: Self::T);
+    /// Set the value to the maximum of the current and the provided value,
+    /// returning the previous value.
+    fn fetch_max(&self, val: Self::T) -> Self::T;
+    /// Set the value to the minimum of the current and the provided value,
+    /// returning the previous value.
+    fn fetch_min(&self, val: Self::T) -> Self::T;
 }
 
 /// A atomic float.
@@ -123,6 +146,37 @@ impl Atomic for AtomicF64 {
     fn dec_by(&self, delta: Self::T) {
         self.inc_by(-delta);
     }
+
+    #[inline]
+    fn fetch_max(&self, val: Self::T) -> Self::T {
+        self.fetch_update_if(|current| val > current, val)
+    }
+
+    #[inline]
+    fn fetch_min(&self, val: Self::T) -> Self::T {
+        self.fetch_update_if(|current| val < current, val)
+    }
+}
+
+impl AtomicF64 {
+    // Replace the value with `val` as long as `cond` holds for the current
+    // value, returning the previous value. A NaN `val` never replaces anything.
+    #[inline]
+    fn fetch_update_if<F: Fn(f64) -> bool>(&self, cond: F, val: f64) -> f64 {
+        let mut current = self.inner.load(Ordering::Relaxed);
+        while cond(u64_to_f64(current)) {
+            match self.inner.compare_exchange_weak(
+                current,
+                f64_to_u64(val),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        u64_to_f64(current)
+    }
 }
 
 /// A atomic signed integer.
@@ -159,6 +213,40 @@ impl Atomic for AtomicI64 {
     fn dec_by(&self, delta: Self::T) {
         self.inner.fetch_sub(delta, Ordering::Relaxed);
     }
+
+    #[inline]
+    fn fetch_max(&self, val: Self::T) -> Self::T {
+        let mut current = self.inner.load(Ordering::Relaxed);
+        while val > current {
+            match self.inner.compare_exchange_weak(
+                current,
+                val,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        current
+    }
+
+    #[inline]
+    fn fetch_min(&self, val: Self::T) -> Self::T {
+        let mut current = self.inner.load(Ordering::Relaxed);
+        while val < current {
+            match self.inner.compare_exchange_weak(
+                current,
+                val,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        current
+    }
 }
 
 /// A atomic unsigned integer.
@@ -195,6 +283,40 @@ impl Atomic for AtomicU64 {
     fn dec_by(&self, delta: Self::T) {
         self.inner.fetch_sub(delta, Ordering::Relaxed);
     }
+
+    #[inline]
+    fn fetch_max(&self, val: Self::T) -> Self::T {
+        let mut current = self.inner.load(Ordering::Relaxed);
+        while val > current {
+            match self.inner.compare_exchange_weak(
+                current,
+                val,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        current
+    }
+
+    #[inline]
+    fn fetch_min(&self, val: Self::T) -> Self::T {
+        let mut current = self.inner.load(Ordering::Relaxed);
+        while val < current {
+            match self.inner.compare_exchange_weak(
+                current,
+                val,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+        current
+    }
 }
 
 #[cfg(test)]
@@ -233,4 +355,50 @@ mod test {
         au64.inc_by(123);
         assert_eq!(au64.get(), 123);
     }
+
+    #[test]
+    fn test_atomic_fetch_max_min() {
+        let af64 = AtomicF64::new(1.0);
+        assert_eq!(af64.fetch_max(2.5), 1.0);
+        assert_eq!(af64.fetch_max(0.5), 2.5);
+        assert_eq!(af64.fetch_max(f64::NAN), 2.5);
+        assert_eq!(af64.fetch_min(-1.0), 2.5);
+        assert_eq!(af64.fetch_min(0.0), -1.0);
+        assert_eq!(af64.get(), -1.0);
+
+        let ai64 = AtomicI64::new(0);
+        assert_eq!(ai64.fetch_max(5), 0);
+        assert_eq!(ai64.fetch_max(3), 5);
+        assert_eq!(ai64.fetch_min(-3), 5);
+        assert_eq!(ai64.fetch_min(1), -3);
+        assert_eq!(ai64.get(), -3);
+
+        let au64 = AtomicU64::new(10);
+        assert_eq!(au64.fetch_max(20), 10);
+        assert_eq!(au64.fetch_min(15), 20);
+        assert_eq!(au64.fetch_min(30), 15);
+        assert_eq!(au64.get(), 15);
+    }
+
+    #[test]
+    fn test_atomic_fetch_max_concurrent() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let ai64 = Arc::new(AtomicI64::new(0));
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let ai64 = Arc::clone(&ai64);
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        ai64.fetch_max(i * 4 + t);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(ai64.get(), 3999);
+    }
 }
@@ -1,5 +1,6 @@
 use crate::core::Atomic;
 use crate::counter::{CounterWithValueType, GenericLocalCounter};
+use crate::gauge::{GaugeWithValueType, GenericLocalGauge};
 use crate::histogram::{Instant, LocalHistogram};
 use crate::metrics::MayFlush;
 use crate::timer;
@@ -15,6 +16,15 @@ pub trait CounterDelegator<T: 'static + MayFlush, V: CounterWithValueType> {
     fn get_local<'a>(&self, root_metric: &'a T) -> &'a GenericLocalCounter<V::ValueType>;
 }
 
+/// Delegator for auto flush-able local gauge
+pub trait GaugeDelegator<T: 'static + MayFlush, V: GaugeWithValueType> {
+    /// Get the root local metric for delegate
+    fn get_root_metric(&self) -> &'static LocalKey<T>;
+
+    /// Get the final gauge for delegate
+    fn get_local<'a>(&self, root_metric: &'a T) -> &'a GenericLocalGauge<V::ValueType>;
+}
+
 /// Delegator for auto flush-able local counter
 pub trait HistogramDelegator<T: 'static + MayFlush> {
     /// Get the root local metric for delegate
@@ -113,6 +123,106 @@ impl<T: 'static + MayFlush, V: CounterWithValueType, D: CounterDelegator<T, V>>
     }
 }
 
+/// Auto flush-able local gauge
+#[derive(Debug)]
+pub struct AFLocalGauge<T: 'static + MayFlush, V: GaugeWithValueType, D: GaugeDelegator<T, V>> {
+    /// Delegator to get thread local metric
+    delegator: D,
+    /// Phantomdata marker
+    _p: std::marker::PhantomData<(Mutex<T>, Mutex<V>)>,
+}
+
+impl<T: 'static + MayFlush, V: GaugeWithValueType, D: GaugeDelegator<T, V>> AFLocalGauge<T, V, D> {
+    /// Construct a new AFLocalGauge from delegator.
+    pub fn new(delegator: D) -> AFLocalGauge<T, V, D> {
+        timer::ensure_updater();
+        AFLocalGauge {
+            delegator,
+            _p: std::marker::PhantomData,
+        }
+    }
+
+    #[inline]
+    /// Get the root local metric for delegate
+    fn get_root_metric(&self) -> &'static LocalKey<T> {
+        self.delegator.get_root_metric()
+    }
+
+    #[inline]
+    /// Get the final gauge for delegate
+    fn get_gauge<'a>(&self, root_metric: &'a T) -> &'a GenericLocalGauge<V::ValueType> {
+        self.delegator.get_local(root_metric)
+    }
+
+    /// Increase the local gauge by 1,
+    /// and try to flush to global.
+    #[inline]
+    pub fn inc(&self) {
+        self.get_root_metric().with(|m| {
+            let gauge = self.get_gauge(m);
+            gauge.inc();
+            m.may_flush();
+        })
+    }
+
+    /// Decrease the local gauge by 1,
+    /// and try to flush to global.
+    #[inline]
+    pub fn dec(&self) {
+        self.get_root_metric().with(|m| {
+            let gauge = self.get_gauge(m);
+            gauge.dec();
+            m.may_flush();
+        })
+    }
+
+    /// Add the given value to the local gauge,
+    /// and try to flush to global.
+    #[inline]
+    pub fn add(&self, v: <V::ValueType as Atomic>::T) {
+        self.get_root_metric().with(|m| {
+            let gauge = self.get_gauge(m);
+            gauge.add(v);
+            m.may_flush();
+        })
+    }
+
+    /// Subtract the given value from the local gauge,
+    /// and try to flush to global.
+    #[inline]
+    pub fn sub(&self, v: <V::ValueType as Atomic>::T) {
+        self.get_root_metric().with(|m| {
+            let gauge = self.get_gauge(m);
+            gauge.sub(v);
+            m.may_flush();
+        })
+    }
+
+    /// Return the delta accumulated since the last flush.
+    #[inline]
+    pub fn get(&self) -> <V::ValueType as Atomic>::T {
+        self.get_root_metric().with(|m| {
+            let gauge = self.get_gauge(m);
+            gauge.get()
+        })
+    }
+
+    /// Discard the delta accumulated since the last flush.
+    #[inline]
+    pub fn reset(&self) {
+        self.get_root_metric().with(|m| {
+            let gauge = self.get_gauge(m);
+            gauge.reset();
+        })
+    }
+
+    /// trigger flush of LocalKey<T>
+    #[inline]
+    pub fn flush(&self) {
+        self.get_root_metric().with(|m| m.flush())
+    }
+}
+
 /// Auto flush-able local counter
 #[derive(Debug)]
 pub struct AFLocalHistogram<T: 'static + MayFlush, D: HistogramDelegator<T>> {
@@ -155,8 +265,10 @@ impl<M: 'static + MayFlush, D: HistogramDelegator<M>> AFLocalHistogram<M, D> {
         res
     }
 
-    /// Observe execution time of a closure, in second.
-    #[cfg(feature = "nightly")]
+    /// Observe execution time of a closure, in second, with the coarse clock.
+    ///
+    /// Without the `nightly` feature on Linux, the coarse time is refreshed
+    /// every 200 milliseconds, see [`SystemClock`](crate::clock::SystemClock).
     pub fn observe_closure_duration_coarse<F, T>(&self, f: F) -> T
     where
         F: FnOnce() -> T,
@@ -196,3 +308,141 @@ impl<M: 'static + MayFlush, D: HistogramDelegator<M>> AFLocalHistogram<M, D> {
             .with(|m| self.delegator.get_local(m).get_sample_count())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::clock;
+    use crate::counter::{IntCounter, LocalIntCounter};
+    use crate::gauge::{IntGauge, LocalIntGauge};
+    use crate::metrics::LocalMetric;
+
+    lazy_static! {
+        static ref COUNTER: IntCounter = IntCounter::new("test_counter", "test help").unwrap();
+        static ref GAUGE: IntGauge = IntGauge::new("test_gauge", "test help").unwrap();
+    }
+
+    struct CounterInner {
+        counter: LocalIntCounter,
+        last_flush: Cell<u64>,
+    }
+
+    impl LocalMetric for CounterInner {
+        fn flush(&self) {
+            self.counter.flush();
+        }
+    }
+
+    impl MayFlush for CounterInner {
+        fn may_flush(&self) {
+            self.try_flush(&self.last_flush, 1000);
+        }
+    }
+
+    thread_local! {
+        static COUNTER_INNER: CounterInner = CounterInner {
+            counter: COUNTER.local(),
+            last_flush: Cell::new(timer::now_millis()),
+        };
+    }
+
+    struct CounterInnerDelegator;
+
+    impl CounterDelegator<CounterInner, LocalIntCounter> for CounterInnerDelegator {
+        fn get_root_metric(&self) -> &'static LocalKey<CounterInner> {
+            &COUNTER_INNER
+        }
+
+        fn get_local<'a>(&self, root_metric: &'a CounterInner) -> &'a LocalIntCounter {
+            &root_metric.counter
+        }
+    }
+
+    struct GaugeInner {
+        gauge: LocalIntGauge,
+        last_flush: Cell<u64>,
+    }
+
+    impl LocalMetric for GaugeInner {
+        fn flush(&self) {
+            self.gauge.flush();
+        }
+    }
+
+    impl MayFlush for GaugeInner {
+        fn may_flush(&self) {
+            self.try_flush(&self.last_flush, 1000);
+        }
+    }
+
+    thread_local! {
+        static GAUGE_INNER: GaugeInner = GaugeInner {
+            gauge: GAUGE.local(),
+            last_flush: Cell::new(timer::now_millis()),
+        };
+    }
+
+    struct GaugeInnerDelegator;
+
+    impl GaugeDelegator<GaugeInner, LocalIntGauge> for GaugeInnerDelegator {
+        fn get_root_metric(&self) -> &'static LocalKey<GaugeInner> {
+            &GAUGE_INNER
+        }
+
+        fn get_local<'a>(&self, root_metric: &'a GaugeInner) -> &'a LocalIntGauge {
+            &root_metric.gauge
+        }
+    }
+
+    #[test]
+    fn test_af_local_counter() {
+        let (clock, _guard) = clock::manual_for_test();
+        let counter = AFLocalCounter::new(CounterInnerDelegator);
+        counter.inc();
+        counter.inc_by(2);
+        assert_eq!(counter.get(), 3);
+        assert_eq!(COUNTER.get(), 0);
+
+        clock.advance(Duration::from_millis(999));
+        counter.inc();
+        assert_eq!(COUNTER.get(), 0);
+
+        clock.advance(Duration::from_millis(1));
+        counter.inc();
+        assert_eq!(counter.get(), 0);
+        assert_eq!(COUNTER.get(), 5);
+
+        counter.inc();
+        counter.flush();
+        assert_eq!(COUNTER.get(), 6);
+    }
+
+    #[test]
+    fn test_af_local_gauge() {
+        let (clock, _guard) = clock::manual_for_test();
+        GAUGE.set(10);
+        let gauge = AFLocalGauge::new(GaugeInnerDelegator);
+        gauge.add(5);
+        gauge.sub(2);
+        gauge.inc();
+        assert_eq!(gauge.get(), 4);
+        assert_eq!(GAUGE.get(), 10);
+
+        clock.advance(Duration::from_millis(999));
+        gauge.dec();
+        assert_eq!(GAUGE.get(), 10);
+
+        // The deltas are added to the shared gauge, not set on it.
+        clock.advance(Duration::from_millis(1));
+        gauge.sub(4);
+        assert_eq!(gauge.get(), 0);
+        assert_eq!(GAUGE.get(), 9);
+
+        gauge.add(3);
+        gauge.flush();
+        assert_eq!(GAUGE.get(), 12);
+    }
+}
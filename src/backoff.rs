@@ -0,0 +1,38 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! The backoff between the retries of the exporters sending over HTTP.
+
+use std::time::Duration;
+
+/// The longest wait between two retries.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Return the wait before the retry following one that waited `backoff`:
+/// twice as long, but at most [`MAX_BACKOFF`].
+pub fn next_backoff(backoff: Duration) -> Duration {
+    backoff
+        .checked_mul(2)
+        .map_or(MAX_BACKOFF, |b| b.min(MAX_BACKOFF))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_backoff() {
+        assert_eq!(
+            next_backoff(Duration::from_millis(100)),
+            Duration::from_millis(200)
+        );
+        assert_eq!(next_backoff(Duration::from_secs(40)), MAX_BACKOFF);
+        assert_eq!(next_backoff(MAX_BACKOFF), MAX_BACKOFF);
+        assert_eq!(next_backoff(Duration::MAX), MAX_BACKOFF);
+
+        let mut backoff = Duration::from_millis(100);
+        for _ in 0..100 {
+            backoff = next_backoff(backoff);
+        }
+        assert_eq!(backoff, MAX_BACKOFF);
+    }
+}
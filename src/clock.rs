@@ -0,0 +1,337 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+/*!
+
+Pluggable source of time for timers and auto-flushing local metrics.
+
+Histogram timers, `observe_closure_duration` and the auto-flush intervals of
+local metrics all read the installed [`Clock`]. By default this is the
+[`SystemClock`], tests can install a [`ManualClock`] instead to control the
+durations they observe without sleeping.
+
+Instants taken before the clock is replaced are not comparable with the new
+clock, so install it before creating timers or local metrics.
+
+*/
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant as StdInstant};
+
+use parking_lot::RwLock;
+
+use crate::timer;
+
+/// A monotonic source of time.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// Return the time elapsed since some fixed point in the past, which is
+    /// specific to the clock.
+    fn now(&self) -> Duration;
+
+    /// Return the time like [`now`](Clock::now), possibly cheaper but less
+    /// precise, with millisecond resolution.
+    fn now_coarse(&self) -> Duration {
+        self.now()
+    }
+}
+
+lazy_static! {
+    static ref ANCHOR: StdInstant = StdInstant::now();
+    static ref CLOCK: RwLock<Arc<dyn Clock>> = RwLock::new(Arc::new(SystemClock));
+}
+
+// Skips the lock as long as the system clock is in use.
+static CUSTOM_CLOCK: AtomicBool = AtomicBool::new(false);
+
+/// The clock of the operating system, which is used by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Duration {
+        StdInstant::now().saturating_duration_since(*ANCHOR)
+    }
+
+    #[cfg(all(feature = "nightly", target_os = "linux"))]
+    fn now_coarse(&self) -> Duration {
+        coarse::now()
+    }
+
+    /// Without `CLOCK_MONOTONIC_COARSE`, the time is the one last read by the
+    /// background thread of [`timer::ensure_updater`], so the resolution is
+    /// the update interval of that thread, 200 milliseconds.
+    #[cfg(not(all(feature = "nightly", target_os = "linux")))]
+    fn now_coarse(&self) -> Duration {
+        timer::ensure_updater();
+        Duration::from_millis(timer::recent_millis())
+    }
+}
+
+#[cfg(all(feature = "nightly", target_os = "linux"))]
+mod coarse {
+    use std::time::Duration;
+
+    use libc::{clock_gettime, timespec, CLOCK_MONOTONIC_COARSE};
+
+    const NANOS_PER_MILLI: i64 = 1_000_000;
+    const MILLIS_PER_SEC: i64 = 1_000;
+
+    fn get_time_coarse_millis() -> i64 {
+        let mut t = timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        assert_eq!(unsafe { clock_gettime(CLOCK_MONOTONIC_COARSE, &mut t) }, 0);
+        t.tv_sec * MILLIS_PER_SEC + t.tv_nsec / NANOS_PER_MILLI
+    }
+
+    lazy_static! {
+        static ref ANCHOR_MILLIS: i64 = get_time_coarse_millis();
+    }
+
+    // Unlike the precise clock, the resolution here is millisecond.
+    // The processors in an SMP system do not start all at exactly the same time
+    // and therefore the timer registers are typically running at an offset.
+    // Use millisecond resolution for ignoring the error.
+    // See more: https://linux.die.net/man/2/clock_gettime
+    pub fn now() -> Duration {
+        let anchor = *ANCHOR_MILLIS;
+        let dur = get_time_coarse_millis() - anchor;
+        Duration::from_millis(dur.max(0) as u64)
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// use prometheus::clock::{self, ManualClock};
+/// use prometheus::{Histogram, HistogramOpts};
+///
+/// let manual = Arc::new(ManualClock::new());
+/// clock::set_clock(manual.clone());
+///
+/// let histogram = Histogram::with_opts(HistogramOpts::new("latency", "help")).unwrap();
+/// let timer = histogram.start_timer();
+/// manual.advance(Duration::from_millis(1500));
+/// assert_eq!(timer.stop_and_record(), 1.5);
+/// # clock::reset_clock();
+/// ```
+#[derive(Debug)]
+pub struct ManualClock {
+    nanos: AtomicU64,
+}
+
+impl ManualClock {
+    /// Create a [`ManualClock`] stopped at the current time of the
+    /// [`SystemClock`].
+    pub fn new() -> ManualClock {
+        ManualClock {
+            nanos: AtomicU64::new(SystemClock.now().as_nanos() as u64),
+        }
+    }
+
+    /// Move the clock forward by `d`.
+    pub fn advance(&self, d: Duration) {
+        self.nanos.fetch_add(d.as_nanos() as u64, Ordering::Relaxed);
+        refresh_recent();
+    }
+
+    /// Move the clock to `now`. Clocks are monotonic, so moving it backwards
+    /// is ignored.
+    pub fn set(&self, now: Duration) {
+        self.nanos
+            .fetch_max(now.as_nanos() as u64, Ordering::Relaxed);
+        refresh_recent();
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+
+    fn now_coarse(&self) -> Duration {
+        let now = self.now();
+        Duration::from_millis(now.as_secs() * 1000 + u64::from(now.subsec_millis()))
+    }
+}
+
+// Local metrics check their flush interval against `timer::recent_millis`,
+// which otherwise only follows a custom clock every few hundred milliseconds.
+fn refresh_recent() {
+    if CUSTOM_CLOCK.load(Ordering::Acquire) {
+        timer::now_millis();
+    }
+}
+
+/// Install `clock` as the clock of this process.
+///
+/// # Examples
+///
+/// The flush interval of local metrics follows the installed clock too:
+///
+/// ```
+/// use std::cell::Cell;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// use prometheus::clock::{self, ManualClock};
+/// use prometheus::local::{LocalIntCounter, LocalMetric, MayFlush};
+/// use prometheus::IntCounter;
+///
+/// struct Local {
+///     counter: LocalIntCounter,
+///     last_flush: Cell<u64>,
+/// }
+///
+/// impl MayFlush for Local {
+///     fn may_flush(&self) {
+///         self.try_flush(&self.last_flush, 1000);
+///     }
+/// }
+///
+/// impl LocalMetric for Local {
+///     fn flush(&self) {
+///         self.counter.flush();
+///     }
+/// }
+///
+/// let manual = Arc::new(ManualClock::new());
+/// clock::set_clock(manual.clone());
+///
+/// let counter = IntCounter::new("requests", "help").unwrap();
+/// let local = Local {
+///     counter: counter.local(),
+///     last_flush: Cell::new(prometheus::timer::recent_millis()),
+/// };
+/// local.counter.inc();
+///
+/// local.may_flush();
+/// assert_eq!(counter.get(), 0);
+/// manual.advance(Duration::from_secs(1));
+/// local.may_flush();
+/// assert_eq!(counter.get(), 1);
+/// # clock::reset_clock();
+/// ```
+pub fn set_clock(clock: Arc<dyn Clock>) {
+    *CLOCK.write() = clock;
+    CUSTOM_CLOCK.store(true, Ordering::Release);
+    refresh_recent();
+}
+
+/// Install the [`SystemClock`] again.
+pub fn reset_clock() {
+    CUSTOM_CLOCK.store(false, Ordering::Release);
+    *CLOCK.write() = Arc::new(SystemClock);
+}
+
+/// Return the time of the installed clock.
+#[inline]
+pub fn now() -> Duration {
+    if CUSTOM_CLOCK.load(Ordering::Acquire) {
+        CLOCK.read().now()
+    } else {
+        SystemClock.now()
+    }
+}
+
+/// Return the coarse time of the installed clock.
+#[inline]
+pub fn now_coarse() -> Duration {
+    if CUSTOM_CLOCK.load(Ordering::Acquire) {
+        CLOCK.read().now_coarse()
+    } else {
+        SystemClock.now_coarse()
+    }
+}
+
+#[cfg(test)]
+lazy_static! {
+    static ref TEST_LOCK: parking_lot::Mutex<()> = parking_lot::Mutex::new(());
+}
+
+/// Serialize the tests that install a clock or depend on the time of the
+/// installed one, which is global.
+#[cfg(test)]
+pub(crate) fn lock_for_test() -> parking_lot::MutexGuard<'static, ()> {
+    TEST_LOCK.lock()
+}
+
+/// Install a [`ManualClock`] for a test. The system clock is installed again
+/// when the guard is dropped.
+#[cfg(test)]
+pub(crate) fn manual_for_test() -> (Arc<ManualClock>, TestClockGuard) {
+    let lock = lock_for_test();
+    let manual = Arc::new(ManualClock::new());
+    set_clock(manual.clone());
+    (manual, TestClockGuard { _lock: lock })
+}
+
+#[cfg(test)]
+pub(crate) struct TestClockGuard {
+    _lock: parking_lot::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl Drop for TestClockGuard {
+    fn drop(&mut self) {
+        reset_clock();
+        timer::reset_recent();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_micros(1500));
+        assert_eq!(clock.now() - start, Duration::from_micros(1500));
+
+        clock.set(start);
+        assert_eq!(clock.now() - start, Duration::from_micros(1500));
+        clock.set(start + Duration::from_secs(2));
+        assert_eq!(clock.now() - start, Duration::from_secs(2));
+
+        let coarse = clock.now_coarse();
+        assert_eq!(coarse.subsec_nanos() % 1_000_000, 0);
+        assert!(coarse <= clock.now());
+    }
+
+    #[test]
+    fn test_system_clock() {
+        let _lock = lock_for_test();
+        let start = SystemClock.now();
+        assert!(SystemClock.now() >= start);
+        assert!(SystemClock.now_coarse() <= SystemClock.now() + Duration::from_millis(10));
+    }
+
+    #[test]
+    #[cfg(not(all(feature = "nightly", target_os = "linux")))]
+    fn test_system_clock_coarse_resolution() {
+        let _lock = lock_for_test();
+        // The coarse time only moves when the updater thread reads the clock.
+        let start = SystemClock.now();
+        let mut readings = vec![SystemClock.now_coarse()];
+        while SystemClock.now() - start < timer::CHECK_UPDATE_INTERVAL * 2 {
+            std::thread::sleep(Duration::from_millis(1));
+            readings.push(SystemClock.now_coarse());
+        }
+        readings.dedup();
+        // Two updates, and maybe the first read of the updater.
+        assert!(readings.len() <= 4, "{:?}", readings);
+    }
+}
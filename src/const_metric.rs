@@ -0,0 +1,319 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::desc::Desc;
+use crate::errors::{Error, Result};
+use crate::histogram::check_bucket_label;
+use crate::metrics::{Collector, Metric};
+use crate::proto::{self, MetricType};
+use crate::value::make_label_pairs;
+
+const QUANTILE_LABEL: &str = "quantile";
+
+/// A [`Metric`] with a fixed value, for custom [`Collector`]s that read
+/// their values from elsewhere at collection time, e.g. from another process
+/// or from a system API.
+///
+/// The label values are validated against a [`Desc`], in the same order as
+/// its variable labels, so collectors don't need to assemble
+/// [`proto::MetricFamily`]s and [`proto::LabelPair`]s by hand.
+///
+/// # Examples
+///
+/// ```
+/// use prometheus::core::Desc;
+/// use prometheus::ConstMetric;
+///
+/// let desc = Desc::new(
+///     "open_files".to_owned(),
+///     "Number of open files.".to_owned(),
+///     vec!["process".to_owned()],
+///     Default::default(),
+/// )
+/// .unwrap();
+///
+/// let metric = ConstMetric::new_gauge(&desc, 42.0, &["server"]).unwrap();
+/// let mf = metric.metric_family();
+/// assert_eq!(mf.get_name(), "open_files");
+/// assert_eq!(mf.get_metric()[0].get_gauge().get_value(), 42.0);
+///
+/// // The label values have to match the variable labels of the desc.
+/// assert!(ConstMetric::new_gauge(&desc, 42.0, &[]).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConstMetric {
+    desc: Desc,
+    metric_type: MetricType,
+    metric: proto::Metric,
+}
+
+impl ConstMetric {
+    /// Create a counter with the given `value`.
+    pub fn new_counter(desc: &Desc, value: f64, label_values: &[&str]) -> Result<ConstMetric> {
+        let mut counter = proto::Counter::default();
+        counter.set_value(value);
+
+        let mut m = ConstMetric::new(desc, MetricType::COUNTER, label_values)?;
+        m.metric.set_counter(counter);
+        Ok(m)
+    }
+
+    /// Create a gauge with the given `value`.
+    pub fn new_gauge(desc: &Desc, value: f64, label_values: &[&str]) -> Result<ConstMetric> {
+        let mut gauge = proto::Gauge::default();
+        gauge.set_value(value);
+
+        let mut m = ConstMetric::new(desc, MetricType::GAUGE, label_values)?;
+        m.metric.set_gauge(gauge);
+        Ok(m)
+    }
+
+    /// Create a histogram with the given sample `count` and `sum`. `buckets`
+    /// are pairs of upper bounds and cumulative counts, in any order. A
+    /// `+Inf` bucket is implied by `count` and may be left out.
+    pub fn new_histogram(
+        desc: &Desc,
+        count: u64,
+        sum: f64,
+        buckets: &[(f64, u64)],
+        label_values: &[&str],
+    ) -> Result<ConstMetric> {
+        for name in &desc.variable_labels {
+            check_bucket_label(name)?;
+        }
+        for pair in &desc.const_label_pairs {
+            check_bucket_label(pair.get_name())?;
+        }
+
+        let mut buckets = buckets.to_vec();
+        if buckets.iter().any(|(upper_bound, _)| upper_bound.is_nan()) {
+            return Err(Error::Msg("histogram bucket upper bound is NaN".to_owned()));
+        }
+        buckets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut h = proto::Histogram::default();
+        h.set_sample_count(count);
+        h.set_sample_sum(sum);
+        h.set_bucket(from_vec!(buckets
+            .into_iter()
+            .map(|(upper_bound, cumulative_count)| {
+                let mut b = proto::Bucket::default();
+                b.set_upper_bound(upper_bound);
+                b.set_cumulative_count(cumulative_count);
+                b
+            })
+            .collect()));
+
+        let mut m = ConstMetric::new(desc, MetricType::HISTOGRAM, label_values)?;
+        m.metric.set_histogram(h);
+        Ok(m)
+    }
+
+    /// Create a summary with the given sample `count` and `sum`. `quantiles`
+    /// are pairs of quantiles (between 0 and 1) and their values, in any
+    /// order.
+    pub fn new_summary(
+        desc: &Desc,
+        count: u64,
+        sum: f64,
+        quantiles: &[(f64, f64)],
+        label_values: &[&str],
+    ) -> Result<ConstMetric> {
+        let quantile_label = desc
+            .variable_labels
+            .iter()
+            .map(String::as_str)
+            .chain(desc.const_label_pairs.iter().map(|p| p.get_name()))
+            .any(|name| name == QUANTILE_LABEL);
+        if quantile_label {
+            return Err(Error::Msg(
+                "`quantile` is not allowed as label name in summaries".to_owned(),
+            ));
+        }
+
+        let mut quantiles = quantiles.to_vec();
+        if let Some((q, _)) = quantiles.iter().find(|(q, _)| !(0.0..=1.0).contains(q)) {
+            return Err(Error::Msg(format!(
+                "summary quantile {} is not between 0 and 1",
+                q
+            )));
+        }
+        quantiles.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut s = proto::Summary::default();
+        s.set_sample_count(count);
+        s.set_sample_sum(sum);
+        s.set_quantile(from_vec!(quantiles
+            .into_iter()
+            .map(|(quantile, value)| {
+                let mut q = proto::Quantile::default();
+                q.set_quantile(quantile);
+                q.set_value(value);
+                q
+            })
+            .collect()));
+
+        let mut m = ConstMetric::new(desc, MetricType::SUMMARY, label_values)?;
+        m.metric.set_summary(s);
+        Ok(m)
+    }
+
+    fn new(desc: &Desc, metric_type: MetricType, label_values: &[&str]) -> Result<ConstMetric> {
+        let label_pairs = make_label_pairs(desc, label_values)?;
+
+        let mut metric = proto::Metric::default();
+        metric.set_label(from_vec!(label_pairs));
+
+        Ok(ConstMetric {
+            desc: desc.clone(),
+            metric_type,
+            metric,
+        })
+    }
+
+    /// Set the time at which the value was measured, in milliseconds since
+    /// the UNIX epoch. By default scrapers assume the value is current.
+    pub fn with_timestamp(mut self, timestamp_ms: i64) -> Self {
+        self.metric.set_timestamp_ms(timestamp_ms);
+        self
+    }
+
+    /// Return the type of this metric.
+    pub fn metric_type(&self) -> MetricType {
+        self.metric_type
+    }
+
+    /// Return a [`proto::MetricFamily`] holding only this metric, ready to be
+    /// returned from [`Collector::collect`]. A [`Registry`](crate::Registry)
+    /// merges the families of several metrics with the same name.
+    pub fn metric_family(&self) -> proto::MetricFamily {
+        let mut m = proto::MetricFamily::default();
+        m.set_name(self.desc.fq_name.clone());
+        m.set_help(self.desc.help.clone());
+        m.set_field_type(self.metric_type);
+        m.set_metric(from_vec!(vec![self.metric()]));
+        m
+    }
+}
+
+impl Metric for ConstMetric {
+    fn metric(&self) -> proto::Metric {
+        self.metric.clone()
+    }
+}
+
+impl Collector for ConstMetric {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![&self.desc]
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        vec![self.metric_family()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::encoder::{Encoder, TextEncoder};
+    use crate::registry::Registry;
+
+    fn desc(name: &str, variable_labels: &[&str]) -> Desc {
+        let mut const_labels = HashMap::new();
+        const_labels.insert("a".to_owned(), "1".to_owned());
+        Desc::new(
+            name.to_owned(),
+            "test help".to_owned(),
+            variable_labels.iter().map(|l| (*l).to_owned()).collect(),
+            const_labels,
+        )
+        .unwrap()
+    }
+
+    struct TestCollector {
+        descs: Vec<Desc>,
+    }
+
+    impl Collector for TestCollector {
+        fn desc(&self) -> Vec<&Desc> {
+            self.descs.iter().collect()
+        }
+
+        fn collect(&self) -> Vec<proto::MetricFamily> {
+            let (counter, histogram) = (&self.descs[0], &self.descs[1]);
+            vec![
+                ConstMetric::new_counter(counter, 2.0, &["y"])
+                    .unwrap()
+                    .metric_family(),
+                ConstMetric::new_counter(counter, 1.0, &["x"])
+                    .unwrap()
+                    .with_timestamp(1_500_000_000_000)
+                    .metric_family(),
+                ConstMetric::new_histogram(histogram, 3, 4.5, &[(2.0, 2), (1.0, 1)], &[])
+                    .unwrap()
+                    .metric_family(),
+            ]
+        }
+    }
+
+    #[test]
+    fn test_const_metric_collector() {
+        let r = Registry::new();
+        r.register(Box::new(TestCollector {
+            descs: vec![desc("test_counter", &["l"]), desc("test_histogram", &[])],
+        }))
+        .unwrap();
+
+        let mut writer = Vec::<u8>::new();
+        TextEncoder::new().encode(&r.gather(), &mut writer).unwrap();
+        let ans = r##"# HELP test_counter test help
+# TYPE test_counter counter
+test_counter{a="1",l="x"} 1 1500000000000
+test_counter{a="1",l="y"} 2
+# HELP test_histogram test help
+# TYPE test_histogram histogram
+test_histogram_bucket{a="1",le="1"} 1
+test_histogram_bucket{a="1",le="2"} 2
+test_histogram_bucket{a="1",le="+Inf"} 3
+test_histogram_sum{a="1"} 4.5
+test_histogram_count{a="1"} 3
+"##;
+        assert_eq!(ans, String::from_utf8(writer).unwrap());
+    }
+
+    #[test]
+    fn test_const_summary() {
+        let m = ConstMetric::new_summary(
+            &desc("test_summary", &[]),
+            10,
+            5.0,
+            &[(0.99, 2.0), (0.5, 0.5)],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(m.metric_type(), MetricType::SUMMARY);
+
+        let metric = m.metric();
+        let s = metric.get_summary();
+        assert_eq!(s.get_sample_count(), 10);
+        assert_eq!(s.get_sample_sum(), 5.0);
+        assert_eq!(s.get_quantile().len(), 2);
+        assert_eq!(s.get_quantile()[0].get_quantile(), 0.5);
+        assert_eq!(s.get_quantile()[1].get_value(), 2.0);
+    }
+
+    #[test]
+    fn test_const_metric_invalid() {
+        let d = desc("test_metric", &["l"]);
+        assert!(ConstMetric::new_counter(&d, 1.0, &[]).is_err());
+        assert!(ConstMetric::new_gauge(&d, 1.0, &["x", "y"]).is_err());
+        assert!(ConstMetric::new_histogram(&d, 1, 1.0, &[(f64::NAN, 1)], &["x"]).is_err());
+        assert!(ConstMetric::new_summary(&d, 1, 1.0, &[(1.5, 1.0)], &["x"]).is_err());
+
+        let d = desc("test_histogram", &["le"]);
+        assert!(ConstMetric::new_histogram(&d, 1, 1.0, &[], &["x"]).is_err());
+        let d = desc("test_summary", &["quantile"]);
+        assert!(ConstMetric::new_summary(&d, 1, 1.0, &[], &["x"]).is_err());
+    }
+}
@@ -88,10 +88,27 @@ impl<P: Atomic> GenericCounter<P> {
         self.v.get()
     }
 
-    /// Restart the counter, resetting its value back to 0.
+    /// Set the time at which the current value was measured, in milliseconds
+    /// since the UNIX epoch, for counters relaying values read from another
+    /// system. 0 clears the timestamp, so that scrapers assume the value is
+    /// current.
+    #[inline]
+    pub fn set_timestamp_ms(&self, timestamp_ms: i64) {
+        self.v.set_timestamp_ms(timestamp_ms);
+    }
+
+    /// Return the explicit timestamp of the counter, in milliseconds since
+    /// the UNIX epoch, or 0 if it has none.
+    #[inline]
+    pub fn get_timestamp_ms(&self) -> i64 {
+        self.v.get_timestamp_ms()
+    }
+
+    /// Restart the counter, resetting its value back to 0 and its creation
+    /// time to now.
     #[inline]
     pub fn reset(&self) {
-        self.v.set(P::T::from_i64(0))
+        self.v.reset()
     }
 
     /// Return a [`GenericLocalCounter`] for single thread usage.
@@ -269,6 +286,12 @@ impl<P: Atomic> Clone for GenericLocalCounter<P> {
     }
 }
 
+impl<P: Atomic> Drop for GenericLocalCounter<P> {
+    fn drop(&mut self) {
+        self.flush()
+    }
+}
+
 /// The underlying implementation for [`LocalCounterVec`]
 /// and [`LocalIntCounterVec`].
 pub struct GenericLocalCounterVec<P: Atomic> {
@@ -485,6 +508,20 @@ mod tests {
         assert_eq!(counter.get() as u64, 0);
     }
 
+    #[test]
+    fn test_counter_with_timestamp() {
+        let counter = IntCounter::new("test_int_counter", "test help").unwrap();
+        counter.inc_by(3);
+        assert_eq!(counter.metric().get_timestamp_ms(), 0);
+
+        counter.set_timestamp_ms(1_500_000_000_000);
+        assert_eq!(counter.get_timestamp_ms(), 1_500_000_000_000);
+        let mfs = counter.collect();
+        let m = &mfs[0].get_metric()[0];
+        assert_eq!(m.get_timestamp_ms(), 1_500_000_000_000);
+        assert_eq!(m.get_counter().get_value() as u64, 3);
+    }
+
     #[test]
     fn test_local_counter() {
         let counter = Counter::new("counter", "counter helper").unwrap();
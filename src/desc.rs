@@ -33,7 +33,7 @@ fn is_valid_metric_name(name: &str) -> bool {
     name.starts_with(valid_start) && !name.contains(|c| !valid_char(c))
 }
 
-fn is_valid_label_name(name: &str) -> bool {
+pub(crate) fn is_valid_label_name(name: &str) -> bool {
     // Valid label names must match regex [a-zA-Z_][a-zA-Z0-9_]*.
     fn valid_start(c: char) -> bool {
         c.is_ascii()
@@ -0,0 +1,286 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::Result;
+use crate::histogram::BUCKET_LABEL;
+use crate::proto::{self, MetricFamily, MetricType};
+
+use super::{check_metric_family, Encoder};
+
+const POSITIVE_INF: &str = "+Inf";
+const QUANTILE: &str = "quantile";
+
+/// How a [`GraphiteEncoder`] maps labels to the metric path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphiteLabels {
+    /// Append the label values, e.g. `http_requests.GET.200`.
+    Values,
+    /// Append the label names and values, e.g.
+    /// `http_requests.method.GET.code.200`.
+    NamesAndValues,
+    /// Use Graphite tags, e.g. `http_requests;method=GET;code=200`.
+    Tags,
+}
+
+/// An implementation of an [`Encoder`] that converts a [`MetricFamily`] proto message
+/// into the Graphite plaintext protocol, one `path value timestamp` line per
+/// sample.
+///
+/// Samples are named like in the text format, e.g. a histogram has
+/// `_bucket`, `_sum` and `_count` samples. Characters that have a meaning in
+/// paths are replaced by `_`. Samples without a timestamp get the time of
+/// the encoding.
+#[derive(Debug)]
+pub struct GraphiteEncoder {
+    prefix: Option<String>,
+    labels: GraphiteLabels,
+}
+
+impl GraphiteEncoder {
+    /// Create a new Graphite encoder, which appends the label values to the
+    /// path.
+    pub fn new() -> GraphiteEncoder {
+        GraphiteEncoder {
+            prefix: None,
+            labels: GraphiteLabels::Values,
+        }
+    }
+
+    /// Prepend `prefix` and a dot to every path.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Set how labels are mapped to the path.
+    pub fn labels(mut self, labels: GraphiteLabels) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    fn write_sample(
+        &self,
+        writer: &mut dyn Write,
+        name: &str,
+        m: &proto::Metric,
+        additional_label: Option<(&str, &str)>,
+        value: f64,
+        now: i64,
+    ) -> Result<()> {
+        let mut path = String::new();
+        if let Some(ref prefix) = self.prefix {
+            path.push_str(prefix);
+            path.push('.');
+        }
+        path.push_str(&sanitize(name));
+
+        let labels = m
+            .get_label()
+            .iter()
+            .map(|lp| (lp.get_name(), lp.get_value()))
+            .chain(additional_label);
+        for (label_name, label_value) in labels {
+            match self.labels {
+                GraphiteLabels::Values => {
+                    path.push('.');
+                    path.push_str(&sanitize(label_value));
+                }
+                GraphiteLabels::NamesAndValues => {
+                    path.push('.');
+                    path.push_str(&sanitize(label_name));
+                    path.push('.');
+                    path.push_str(&sanitize(label_value));
+                }
+                // Empty tag values are not allowed.
+                GraphiteLabels::Tags if label_value.is_empty() => {}
+                GraphiteLabels::Tags => {
+                    path.push(';');
+                    path.push_str(&sanitize_tag(label_name));
+                    path.push('=');
+                    path.push_str(&sanitize_tag(label_value));
+                }
+            }
+        }
+
+        let timestamp = if m.get_timestamp_ms() != 0 {
+            m.get_timestamp_ms() / 1000
+        } else {
+            now
+        };
+        writeln!(writer, "{} {} {}", path, value, timestamp)?;
+        Ok(())
+    }
+}
+
+impl Default for GraphiteEncoder {
+    fn default() -> Self {
+        GraphiteEncoder::new()
+    }
+}
+
+impl Encoder for GraphiteEncoder {
+    fn encode<W: Write>(&self, metric_families: &[MetricFamily], writer: &mut W) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+
+        for mf in metric_families {
+            // Fail-fast checks.
+            check_metric_family(mf)?;
+
+            let name = mf.get_name();
+            for m in mf.get_metric() {
+                match mf.get_field_type() {
+                    MetricType::COUNTER => {
+                        let value = m.get_counter().get_value();
+                        self.write_sample(writer, name, m, None, value, now)?;
+                    }
+                    MetricType::GAUGE => {
+                        let value = m.get_gauge().get_value();
+                        self.write_sample(writer, name, m, None, value, now)?;
+                    }
+                    MetricType::UNTYPED => {
+                        #[allow(deprecated)]
+                        let value = m.get_untyped().get_value();
+                        self.write_sample(writer, name, m, None, value, now)?;
+                    }
+                    MetricType::HISTOGRAM | MetricType::GAUGE_HISTOGRAM => {
+                        let h = m.get_histogram();
+                        let bucket_name = format!("{}_bucket", name);
+                        let mut inf_seen = false;
+                        for b in h.get_bucket() {
+                            let upper_bound = b.get_upper_bound();
+                            let count = b.get_cumulative_count() as f64;
+                            if upper_bound == f64::INFINITY {
+                                inf_seen = true;
+                            }
+                            let le = if inf_seen {
+                                POSITIVE_INF.to_owned()
+                            } else {
+                                upper_bound.to_string()
+                            };
+                            let label = Some((BUCKET_LABEL, le.as_str()));
+                            self.write_sample(writer, &bucket_name, m, label, count, now)?;
+                        }
+                        if !inf_seen {
+                            let label = Some((BUCKET_LABEL, POSITIVE_INF));
+                            let count = h.get_sample_count() as f64;
+                            self.write_sample(writer, &bucket_name, m, label, count, now)?;
+                        }
+
+                        let sum_name = format!("{}_sum", name);
+                        let sum = h.get_sample_sum();
+                        self.write_sample(writer, &sum_name, m, None, sum, now)?;
+                        let count_name = format!("{}_count", name);
+                        let count = h.get_sample_count() as f64;
+                        self.write_sample(writer, &count_name, m, None, count, now)?;
+                    }
+                    MetricType::SUMMARY => {
+                        let s = m.get_summary();
+                        for q in s.get_quantile() {
+                            let quantile = q.get_quantile().to_string();
+                            let label = Some((QUANTILE, quantile.as_str()));
+                            self.write_sample(writer, name, m, label, q.get_value(), now)?;
+                        }
+
+                        let sum_name = format!("{}_sum", name);
+                        let sum = s.get_sample_sum();
+                        self.write_sample(writer, &sum_name, m, None, sum, now)?;
+                        let count_name = format!("{}_count", name);
+                        let count = s.get_sample_count() as f64;
+                        self.write_sample(writer, &count_name, m, None, count, now)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn format_type(&self) -> &str {
+        "text/plain; charset=utf-8"
+    }
+}
+
+// Replaces the characters that separate path nodes, fields or lines.
+fn sanitize(s: &str) -> String {
+    s.replace(|c: char| c == '.' || c == '/' || c.is_whitespace(), "_")
+}
+
+fn sanitize_tag(s: &str) -> String {
+    s.replace(
+        |c: char| c == ';' || c == '~' || c == '=' || c.is_whitespace(),
+        "_",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::CounterVec;
+    use crate::histogram::{Histogram, HistogramOpts};
+    use crate::metrics::{Collector, Opts};
+
+    #[test]
+    fn test_graphite_encoder() {
+        let cv =
+            CounterVec::new(Opts::new("test_counter", "test help"), &["method", "path"]).unwrap();
+        let c = cv.with_label_values(&["GET", "/a.b"]);
+        c.inc();
+        c.set_timestamp_ms(1_500_000_000_123);
+
+        let mut writer = Vec::<u8>::new();
+        let encoder = GraphiteEncoder::new().prefix("app");
+        encoder.encode(&cv.collect(), &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "app.test_counter.GET._a_b 1 1500000000\n"
+        );
+
+        let mut writer = Vec::<u8>::new();
+        let encoder = GraphiteEncoder::new().labels(GraphiteLabels::NamesAndValues);
+        encoder.encode(&cv.collect(), &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "test_counter.method.GET.path._a_b 1 1500000000\n"
+        );
+
+        let mut writer = Vec::<u8>::new();
+        let encoder = GraphiteEncoder::new().labels(GraphiteLabels::Tags);
+        encoder.encode(&cv.collect(), &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "test_counter;method=GET;path=/a.b 1 1500000000\n"
+        );
+    }
+
+    #[test]
+    fn test_graphite_encoder_histogram() {
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("test_histogram", "test help").buckets(vec![0.5]),
+        )
+        .unwrap();
+        histogram.observe(0.25);
+
+        let mut writer = Vec::<u8>::new();
+        let encoder = GraphiteEncoder::new().labels(GraphiteLabels::Tags);
+        encoder.encode(&histogram.collect(), &mut writer).unwrap();
+        let paths: Vec<_> = String::from_utf8(writer)
+            .unwrap()
+            .lines()
+            .map(|l| l.rsplit_once(' ').unwrap().0.to_owned())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "test_histogram_bucket;le=0.5 1",
+                "test_histogram_bucket;le=+Inf 1",
+                "test_histogram_sum 0.25",
+                "test_histogram_count 1",
+            ]
+        );
+    }
+}
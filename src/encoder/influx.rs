@@ -0,0 +1,195 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::Result;
+use crate::proto::{MetricFamily, MetricType};
+
+use super::{check_metric_family, Encoder};
+
+const POSITIVE_INF: &str = "+Inf";
+
+/// An implementation of an [`Encoder`] that converts a [`MetricFamily`] proto message
+/// into the InfluxDB line protocol.
+///
+/// Every metric becomes a line whose measurement is the name of the family
+/// and whose tags are the labels, like the Prometheus input of Telegraf
+/// does. The fields are:
+///
+/// * `counter`, `gauge` or `value` for counters, gauges and untyped metrics;
+/// * `sum`, `count` and a field per quantile, named after it, for summaries;
+/// * `sum`, `count` and a field per bucket, named after its upper bound, for
+///   histograms. Buckets are cumulative.
+///
+/// Fields that are not finite are skipped, InfluxDB cannot store them.
+/// Metrics without a timestamp get the time of the encoding.
+#[derive(Debug, Default)]
+pub struct InfluxLineEncoder;
+
+impl InfluxLineEncoder {
+    /// Create a new InfluxDB line protocol encoder.
+    pub fn new() -> InfluxLineEncoder {
+        InfluxLineEncoder
+    }
+}
+
+impl Encoder for InfluxLineEncoder {
+    fn encode<W: Write>(&self, metric_families: &[MetricFamily], writer: &mut W) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as i64)
+            .unwrap_or_default();
+
+        for mf in metric_families {
+            // Fail-fast checks.
+            check_metric_family(mf)?;
+
+            let measurement = escape(mf.get_name(), false);
+            for m in mf.get_metric() {
+                let mut fields = Vec::new();
+                match mf.get_field_type() {
+                    MetricType::COUNTER => {
+                        fields.push(("counter".to_owned(), m.get_counter().get_value()))
+                    }
+                    MetricType::GAUGE => {
+                        fields.push(("gauge".to_owned(), m.get_gauge().get_value()))
+                    }
+                    MetricType::UNTYPED => {
+                        #[allow(deprecated)]
+                        let value = m.get_untyped().get_value();
+                        fields.push(("value".to_owned(), value))
+                    }
+                    MetricType::HISTOGRAM | MetricType::GAUGE_HISTOGRAM => {
+                        let h = m.get_histogram();
+                        fields.push(("sum".to_owned(), h.get_sample_sum()));
+                        fields.push(("count".to_owned(), h.get_sample_count() as f64));
+                        let mut inf_seen = false;
+                        for b in h.get_bucket() {
+                            let upper_bound = b.get_upper_bound();
+                            let name = if upper_bound == f64::INFINITY {
+                                inf_seen = true;
+                                POSITIVE_INF.to_owned()
+                            } else {
+                                upper_bound.to_string()
+                            };
+                            fields.push((name, b.get_cumulative_count() as f64));
+                        }
+                        if !inf_seen {
+                            fields.push((POSITIVE_INF.to_owned(), h.get_sample_count() as f64));
+                        }
+                    }
+                    MetricType::SUMMARY => {
+                        let s = m.get_summary();
+                        fields.push(("sum".to_owned(), s.get_sample_sum()));
+                        fields.push(("count".to_owned(), s.get_sample_count() as f64));
+                        for q in s.get_quantile() {
+                            fields.push((q.get_quantile().to_string(), q.get_value()));
+                        }
+                    }
+                }
+
+                fields.retain(|(_, value)| value.is_finite());
+                if fields.is_empty() {
+                    continue;
+                }
+
+                writer.write_all(measurement.as_bytes())?;
+                for lp in m.get_label() {
+                    // Empty tag values are not allowed.
+                    if lp.get_value().is_empty() {
+                        continue;
+                    }
+                    write!(
+                        writer,
+                        ",{}={}",
+                        escape(lp.get_name(), true),
+                        escape(lp.get_value(), true)
+                    )?;
+                }
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    let separator = if i == 0 { ' ' } else { ',' };
+                    write!(writer, "{}{}={}", separator, escape(name, true), value)?;
+                }
+
+                let timestamp = if m.get_timestamp_ms() != 0 {
+                    m.get_timestamp_ms() * 1_000_000
+                } else {
+                    now
+                };
+                writeln!(writer, " {}", timestamp)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn format_type(&self) -> &str {
+        "text/plain; charset=utf-8"
+    }
+}
+
+// Escapes commas and spaces, and equal signs in keys and tag values.
+fn escape(s: &str, escape_equal: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            ',' | ' ' | '\\' => escaped.push('\\'),
+            '=' if escape_equal => escaped.push('\\'),
+            // Newlines cannot be escaped, they become escaped spaces.
+            '\n' => {
+                escaped.push_str("\\ ");
+                continue;
+            }
+            _ => {}
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::CounterVec;
+    use crate::histogram::{Histogram, HistogramOpts};
+    use crate::metrics::{Collector, Opts};
+
+    #[test]
+    fn test_influx_line_encoder() {
+        let cv = CounterVec::new(Opts::new("test_counter", "test help"), &["a", "b"]).unwrap();
+        let c = cv.with_label_values(&["x y", ""]);
+        c.inc_by(2.0);
+        c.set_timestamp_ms(1_500_000_000_123);
+
+        let mut writer = Vec::<u8>::new();
+        let encoder = InfluxLineEncoder::new();
+        encoder.encode(&cv.collect(), &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "test_counter,a=x\\ y counter=2 1500000000123000000\n"
+        );
+
+        let cv = CounterVec::new(Opts::new("test_counter", "test help"), &["a"]).unwrap();
+        let c = cv.with_label_values(&["x\ny"]);
+        c.inc();
+        c.set_timestamp_ms(1_500_000_000_123);
+        let mut writer = Vec::<u8>::new();
+        encoder.encode(&cv.collect(), &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "test_counter,a=x\\ y counter=1 1500000000123000000\n"
+        );
+
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("test_histogram", "test help").buckets(vec![0.5]),
+        )
+        .unwrap();
+        histogram.observe(0.25);
+        histogram.observe(1.0);
+        let mut writer = Vec::<u8>::new();
+        encoder.encode(&histogram.collect(), &mut writer).unwrap();
+        let line = String::from_utf8(writer).unwrap();
+        assert!(line.starts_with("test_histogram sum=1.25,count=2,0.5=1,+Inf=2 "));
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::io::Write;
+
+use serde_json::{json, Map, Value};
+
+use crate::errors::{Error, Result};
+use crate::proto::{self, MetricFamily, MetricType};
+
+use super::{check_metric_family, Encoder};
+
+/// The JSON format of metric family.
+pub const JSON_FORMAT: &str = "application/json";
+
+const POSITIVE_INF: &str = "+Inf";
+
+/// An implementation of an [`Encoder`] that converts a [`MetricFamily`] proto message
+/// into JSON, in the format of
+/// [prom2json](https://github.com/prometheus/prom2json).
+///
+/// The metric families are written as an array of objects with a `name`, a
+/// `help`, a `type` and `metrics`. Every metric has its `labels` as an
+/// object, and either a `value`, or `count`, `sum` and `buckets` or
+/// `quantiles` objects. Values are strings, so that `NaN` and `+Inf` can be
+/// represented.
+///
+/// ```json
+/// [{"name":"requests_total","help":"Number of requests.","type":"COUNTER",
+///   "metrics":[{"labels":{"code":"200"},"value":"42"}]}]
+/// ```
+///
+/// Unlike the serde implementations of the metric model, the format is the
+/// same whether the `protobuf` feature is enabled or not.
+#[derive(Debug, Default)]
+pub struct JsonEncoder;
+
+impl JsonEncoder {
+    /// Create a new JSON encoder.
+    pub fn new() -> JsonEncoder {
+        JsonEncoder
+    }
+}
+
+impl Encoder for JsonEncoder {
+    fn encode<W: Write>(&self, metric_families: &[MetricFamily], writer: &mut W) -> Result<()> {
+        let mut families = Vec::with_capacity(metric_families.len());
+        for mf in metric_families {
+            // Fail-fast checks.
+            check_metric_family(mf)?;
+
+            let metric_type = mf.get_field_type();
+            let metrics: Vec<_> = mf
+                .get_metric()
+                .iter()
+                .map(|m| metric_to_json(metric_type, m))
+                .collect();
+            families.push(json!({
+                "name": mf.get_name(),
+                "help": mf.get_help(),
+                "type": format!("{:?}", metric_type),
+                "metrics": metrics,
+            }));
+        }
+
+        serde_json::to_writer(writer, &families).map_err(|e| Error::Msg(format!("{}", e)))
+    }
+
+    fn format_type(&self) -> &str {
+        JSON_FORMAT
+    }
+}
+
+fn metric_to_json(metric_type: MetricType, m: &proto::Metric) -> Value {
+    let labels: Map<_, _> = m
+        .get_label()
+        .iter()
+        .map(|lp| (lp.get_name().to_owned(), Value::from(lp.get_value())))
+        .collect();
+    let mut metric = Map::new();
+    metric.insert("labels".to_owned(), Value::Object(labels));
+
+    match metric_type {
+        MetricType::COUNTER => {
+            metric.insert("value".to_owned(), number(m.get_counter().get_value()));
+        }
+        MetricType::GAUGE => {
+            metric.insert("value".to_owned(), number(m.get_gauge().get_value()));
+        }
+        MetricType::UNTYPED => {
+            #[allow(deprecated)]
+            let value = m.get_untyped().get_value();
+            metric.insert("value".to_owned(), number(value));
+        }
+        MetricType::SUMMARY => {
+            let s = m.get_summary();
+            let quantiles: Map<_, _> = s
+                .get_quantile()
+                .iter()
+                .map(|q| (q.get_quantile().to_string(), number(q.get_value())))
+                .collect();
+            metric.insert("quantiles".to_owned(), Value::Object(quantiles));
+            metric.insert("count".to_owned(), s.get_sample_count().to_string().into());
+            metric.insert("sum".to_owned(), number(s.get_sample_sum()));
+        }
+        MetricType::HISTOGRAM | MetricType::GAUGE_HISTOGRAM => {
+            let h = m.get_histogram();
+            let mut buckets = Map::new();
+            for b in h.get_bucket() {
+                let upper_bound = b.get_upper_bound();
+                let le = if upper_bound == f64::INFINITY {
+                    POSITIVE_INF.to_owned()
+                } else {
+                    upper_bound.to_string()
+                };
+                buckets.insert(le, b.get_cumulative_count().to_string().into());
+            }
+            buckets
+                .entry(POSITIVE_INF)
+                .or_insert_with(|| h.get_sample_count().to_string().into());
+            metric.insert("buckets".to_owned(), Value::Object(buckets));
+            metric.insert("count".to_owned(), h.get_sample_count().to_string().into());
+            metric.insert("sum".to_owned(), number(h.get_sample_sum()));
+        }
+    }
+
+    if m.get_timestamp_ms() != 0 {
+        metric.insert(
+            "timestamp_ms".to_owned(),
+            m.get_timestamp_ms().to_string().into(),
+        );
+    }
+    Value::Object(metric)
+}
+
+fn number(v: f64) -> Value {
+    let s = if v == f64::INFINITY {
+        POSITIVE_INF.to_owned()
+    } else if v == f64::NEG_INFINITY {
+        "-Inf".to_owned()
+    } else {
+        v.to_string()
+    };
+    Value::String(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::CounterVec;
+    use crate::histogram::{Histogram, HistogramOpts};
+    use crate::metrics::{Collector, Opts};
+
+    #[test]
+    fn test_json_encoder() {
+        let cv = CounterVec::new(Opts::new("test_counter", "test help"), &["a"]).unwrap();
+        let c = cv.with_label_values(&["x"]);
+        c.inc_by(2.0);
+        c.set_timestamp_ms(42);
+
+        let mut writer = Vec::<u8>::new();
+        let encoder = JsonEncoder::new();
+        encoder.encode(&cv.collect(), &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"[{"help":"test help","metrics":[{"labels":{"a":"x"},"timestamp_ms":"42","value":"2"}],"name":"test_counter","type":"COUNTER"}]"#
+        );
+
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("test_histogram", "test help").buckets(vec![0.5]),
+        )
+        .unwrap();
+        histogram.observe(0.25);
+        histogram.observe(f64::INFINITY);
+        let mut writer = Vec::<u8>::new();
+        encoder.encode(&histogram.collect(), &mut writer).unwrap();
+        let value: Value = serde_json::from_slice(&writer).unwrap();
+        assert_eq!(
+            value[0]["metrics"][0],
+            json!({
+                "labels": {},
+                "buckets": {"0.5": "1", "+Inf": "2"},
+                "count": "2",
+                "sum": "+Inf",
+            })
+        );
+    }
+
+    #[test]
+    fn test_serde_metric_family() {
+        let cv = CounterVec::new(Opts::new("test_counter", "test help"), &["a"]).unwrap();
+        cv.with_label_values(&["x"]).inc();
+
+        let mfs = cv.collect();
+        let json = serde_json::to_string(&mfs).unwrap();
+        let decoded: Vec<MetricFamily> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, mfs);
+    }
+}
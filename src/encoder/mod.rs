@@ -1,9 +1,19 @@
 // Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
 
+mod graphite;
+mod influx;
+#[cfg(feature = "serde")]
+mod json;
+mod openmetrics;
 #[cfg(feature = "protobuf")]
 mod pb;
 mod text;
 
+pub use self::graphite::{GraphiteEncoder, GraphiteLabels};
+pub use self::influx::InfluxLineEncoder;
+#[cfg(feature = "serde")]
+pub use self::json::{JsonEncoder, JSON_FORMAT};
+pub use self::openmetrics::{OpenMetricsEncoder, OPENMETRICS_FORMAT};
 #[cfg(feature = "protobuf")]
 pub use self::pb::{ProtobufEncoder, PROTOBUF_FORMAT};
 pub use self::text::{TextEncoder, TEXT_FORMAT};
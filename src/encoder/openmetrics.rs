@@ -0,0 +1,366 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::io::Write;
+
+use crate::errors::Result;
+use crate::histogram::BUCKET_LABEL;
+use crate::proto::{self, MetricFamily, MetricType};
+
+use super::text::{escape_string, label_pairs_to_text};
+use super::{check_metric_family, Encoder};
+
+/// The OpenMetrics text format of metric family.
+pub const OPENMETRICS_FORMAT: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+const POSITIVE_INF: &str = "+Inf";
+const QUANTILE: &str = "quantile";
+const TOTAL_SUFFIX: &str = "_total";
+
+/// An implementation of an [`Encoder`] that converts a [`MetricFamily`] proto message
+/// into the [OpenMetrics](https://openmetrics.io) text format.
+///
+/// Unlike the [`TextEncoder`](crate::TextEncoder), it exposes gauge histograms
+/// with their own `gaugehistogram` type.
+#[derive(Debug, Default)]
+pub struct OpenMetricsEncoder;
+
+impl OpenMetricsEncoder {
+    /// Create a new OpenMetrics encoder.
+    pub fn new() -> OpenMetricsEncoder {
+        OpenMetricsEncoder
+    }
+}
+
+impl Encoder for OpenMetricsEncoder {
+    fn encode<W: Write>(&self, metric_families: &[MetricFamily], writer: &mut W) -> Result<()> {
+        for mf in metric_families {
+            // Fail-fast checks.
+            check_metric_family(mf)?;
+
+            let metric_type = mf.get_field_type();
+            let name = mf.get_name();
+
+            // Counter samples carry the `_total` suffix, the family does not.
+            let family_name = match metric_type {
+                MetricType::COUNTER if name.ends_with(TOTAL_SUFFIX) => {
+                    &name[..name.len() - TOTAL_SUFFIX.len()]
+                }
+                _ => name,
+            };
+
+            // Write `# TYPE` header.
+            writer.write_all(b"# TYPE ")?;
+            writer.write_all(family_name.as_bytes())?;
+            writer.write_all(b" ")?;
+            writer.write_all(type_name(metric_type).as_bytes())?;
+            writer.write_all(b"\n")?;
+
+            // Write `# HELP` header.
+            let help = mf.get_help();
+            if !help.is_empty() {
+                writer.write_all(b"# HELP ")?;
+                writer.write_all(family_name.as_bytes())?;
+                writer.write_all(b" ")?;
+                writer.write_all(escape_string(help, true).as_bytes())?;
+                writer.write_all(b"\n")?;
+            }
+
+            for m in mf.get_metric() {
+                match metric_type {
+                    MetricType::COUNTER => {
+                        write_sample(
+                            writer,
+                            family_name,
+                            Some(TOTAL_SUFFIX),
+                            m,
+                            None,
+                            m.get_counter().get_value(),
+                        )?;
+
+                        let c = m.get_counter();
+                        if c.has_created_timestamp() {
+                            let ts = c.get_created_timestamp();
+                            write_created(
+                                writer,
+                                family_name,
+                                m,
+                                ts.get_seconds(),
+                                ts.get_nanos(),
+                            )?;
+                        }
+                    }
+                    MetricType::GAUGE => {
+                        write_sample(writer, name, None, m, None, m.get_gauge().get_value())?;
+                    }
+                    MetricType::HISTOGRAM | MetricType::GAUGE_HISTOGRAM => {
+                        let h = m.get_histogram();
+
+                        let mut inf_seen = false;
+                        for b in h.get_bucket() {
+                            let upper_bound = b.get_upper_bound();
+                            write_sample(
+                                writer,
+                                name,
+                                Some("_bucket"),
+                                m,
+                                Some((BUCKET_LABEL, &format_value(upper_bound))),
+                                b.get_cumulative_count() as f64,
+                            )?;
+                            if upper_bound.is_sign_positive() && upper_bound.is_infinite() {
+                                inf_seen = true;
+                            }
+                        }
+                        if !inf_seen {
+                            write_sample(
+                                writer,
+                                name,
+                                Some("_bucket"),
+                                m,
+                                Some((BUCKET_LABEL, POSITIVE_INF)),
+                                h.get_sample_count() as f64,
+                            )?;
+                        }
+
+                        let (count_postfix, sum_postfix) = if metric_type == MetricType::HISTOGRAM {
+                            ("_count", "_sum")
+                        } else {
+                            ("_gcount", "_gsum")
+                        };
+
+                        write_sample(
+                            writer,
+                            name,
+                            Some(count_postfix),
+                            m,
+                            None,
+                            h.get_sample_count() as f64,
+                        )?;
+
+                        write_sample(writer, name, Some(sum_postfix), m, None, h.get_sample_sum())?;
+
+                        // Gauge histograms are not cumulative over time.
+                        if metric_type == MetricType::HISTOGRAM && h.has_created_timestamp() {
+                            let ts = h.get_created_timestamp();
+                            write_created(writer, name, m, ts.get_seconds(), ts.get_nanos())?;
+                        }
+                    }
+                    MetricType::SUMMARY => {
+                        let s = m.get_summary();
+
+                        for q in s.get_quantile() {
+                            write_sample(
+                                writer,
+                                name,
+                                None,
+                                m,
+                                Some((QUANTILE, &format_value(q.get_quantile()))),
+                                q.get_value(),
+                            )?;
+                        }
+
+                        write_sample(
+                            writer,
+                            name,
+                            Some("_count"),
+                            m,
+                            None,
+                            s.get_sample_count() as f64,
+                        )?;
+
+                        write_sample(writer, name, Some("_sum"), m, None, s.get_sample_sum())?;
+
+                        if s.has_created_timestamp() {
+                            let ts = s.get_created_timestamp();
+                            write_created(writer, name, m, ts.get_seconds(), ts.get_nanos())?;
+                        }
+                    }
+                    MetricType::UNTYPED => {
+                        #[allow(deprecated)]
+                        let value = m.get_untyped().get_value();
+                        write_sample(writer, name, None, m, None, value)?;
+                    }
+                }
+            }
+        }
+
+        writer.write_all(b"# EOF\n")?;
+
+        Ok(())
+    }
+
+    fn format_type(&self) -> &str {
+        OPENMETRICS_FORMAT
+    }
+}
+
+/// `type_name` returns the OpenMetrics name of a metric type.
+fn type_name(metric_type: MetricType) -> &'static str {
+    match metric_type {
+        MetricType::COUNTER => "counter",
+        MetricType::GAUGE => "gauge",
+        MetricType::SUMMARY => "summary",
+        MetricType::UNTYPED => "unknown",
+        MetricType::HISTOGRAM => "histogram",
+        MetricType::GAUGE_HISTOGRAM => "gaugehistogram",
+    }
+}
+
+/// `format_value` formats a float as required by OpenMetrics, which spells
+/// the special values `+Inf`, `-Inf` and `NaN`.
+fn format_value(v: f64) -> String {
+    if v.is_nan() {
+        "NaN".to_owned()
+    } else if v.is_infinite() {
+        if v.is_sign_positive() {
+            POSITIVE_INF.to_owned()
+        } else {
+            "-Inf".to_owned()
+        }
+    } else {
+        v.to_string()
+    }
+}
+
+/// `write_sample` writes a single sample in OpenMetrics text format to
+/// `writer`, given the metric name, an optional metric name postfix, the
+/// metric proto message itself, optionally an additional label name and
+/// value, and the value. Timestamps are written in seconds.
+fn write_sample(
+    writer: &mut dyn Write,
+    name: &str,
+    name_postfix: Option<&str>,
+    mc: &proto::Metric,
+    additional_label: Option<(&str, &str)>,
+    value: f64,
+) -> Result<()> {
+    writer.write_all(name.as_bytes())?;
+    if let Some(postfix) = name_postfix {
+        writer.write_all(postfix.as_bytes())?;
+    }
+
+    label_pairs_to_text(mc.get_label(), additional_label, writer)?;
+
+    writer.write_all(b" ")?;
+    writer.write_all(format_value(value).as_bytes())?;
+
+    let timestamp = mc.get_timestamp_ms();
+    if timestamp != 0 {
+        writer.write_all(b" ")?;
+        writer.write_all(format_value(timestamp as f64 / 1000.0).as_bytes())?;
+    }
+
+    writer.write_all(b"\n")?;
+
+    Ok(())
+}
+
+/// `write_created` writes the `_created` sample of a cumulative metric, whose
+/// value is its creation time in seconds.
+fn write_created(
+    writer: &mut dyn Write,
+    name: &str,
+    mc: &proto::Metric,
+    seconds: i64,
+    nanos: i32,
+) -> Result<()> {
+    let created = seconds as f64 + f64::from(nanos) / 1e9;
+    write_sample(writer, name, Some("_created"), mc, None, created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::Counter;
+    use crate::gauge::Gauge;
+    use crate::histogram::{Histogram, HistogramOpts};
+    use crate::metrics::{Collector, Opts};
+
+    #[test]
+    fn test_openmetrics_encoder() {
+        let counter_opts = Opts::new("test_counter_total", "test help")
+            .const_label("a", "1")
+            .const_label("b", "2");
+        let counter = Counter::with_opts(counter_opts).unwrap();
+        counter.inc();
+
+        let gauge = Gauge::new("test_gauge", "test \"help\"").unwrap();
+        gauge.set(f64::INFINITY);
+
+        let mut mfs = counter.collect();
+        let mut c = mfs[0].get_metric()[0].get_counter().clone();
+        c.mut_created_timestamp().set_seconds(1_500_000_000);
+        c.mut_created_timestamp().set_nanos(500_000_000);
+        mfs[0].mut_metric()[0].set_counter(c);
+        mfs.extend(gauge.collect());
+        let mut writer = Vec::<u8>::new();
+        let encoder = OpenMetricsEncoder::new();
+        encoder.encode(&mfs, &mut writer).unwrap();
+
+        let ans = r##"# TYPE test_counter counter
+# HELP test_counter test help
+test_counter_total{a="1",b="2"} 1
+test_counter_created{a="1",b="2"} 1500000000.5
+# TYPE test_gauge gauge
+# HELP test_gauge test \"help\"
+test_gauge +Inf
+# EOF
+"##;
+        assert_eq!(ans, String::from_utf8(writer).unwrap());
+    }
+
+    #[test]
+    fn test_openmetrics_encoder_histogram() {
+        let opts = HistogramOpts::new("test_histogram", "test help")
+            .const_label("a", "1")
+            .buckets(vec![0.5, 1.0]);
+        let histogram = Histogram::with_opts(opts).unwrap();
+        histogram.observe(0.25);
+        histogram.observe(2.0);
+
+        let mut mf = histogram.collect();
+        let mut h = mf[0].get_metric()[0].get_histogram().clone();
+        h.mut_created_timestamp().set_seconds(1_500_000_000);
+        h.mut_created_timestamp().set_nanos(0);
+        mf[0].mut_metric()[0].set_histogram(h);
+        let mut writer = Vec::<u8>::new();
+        let encoder = OpenMetricsEncoder::new();
+        encoder.encode(&mf, &mut writer).unwrap();
+
+        let ans = r##"# TYPE test_histogram histogram
+# HELP test_histogram test help
+test_histogram_bucket{a="1",le="0.5"} 1
+test_histogram_bucket{a="1",le="1"} 1
+test_histogram_bucket{a="1",le="+Inf"} 2
+test_histogram_count{a="1"} 2
+test_histogram_sum{a="1"} 2.25
+test_histogram_created{a="1"} 1500000000
+# EOF
+"##;
+        assert_eq!(ans, String::from_utf8(writer).unwrap());
+    }
+
+    #[test]
+    fn test_openmetrics_encoder_created_reset() {
+        let counter = Counter::new("test_counter", "test help").unwrap();
+        let created = |c: &Counter| {
+            let mfs = c.collect();
+            let ts = mfs[0].get_metric()[0].get_counter().get_created_timestamp();
+            (ts.get_seconds(), ts.get_nanos())
+        };
+        let before = created(&counter);
+        assert!(before.0 > 0);
+
+        counter.inc();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        counter.reset();
+        assert_eq!(counter.get(), 0.0);
+        assert!(created(&counter) > before);
+
+        let mut writer = Vec::<u8>::new();
+        OpenMetricsEncoder::new()
+            .encode(&counter.collect(), &mut writer)
+            .unwrap();
+        let output = String::from_utf8(writer).unwrap();
+        assert!(output.contains("\ntest_counter_created "));
+    }
+}
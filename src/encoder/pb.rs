@@ -59,7 +59,11 @@ mod tests {
         reg.register(Box::new(cv.clone())).unwrap();
 
         cv.get_metric_with_label_values(&["2230"]).unwrap().inc();
-        let mf = reg.gather();
+        let mut mf = reg.gather();
+        // The golden output predates created timestamps.
+        let mut counter = mf[0].get_metric()[0].get_counter().clone();
+        counter.clear_created_timestamp();
+        mf[0].mut_metric()[0].set_counter(counter);
         let mut writer = Vec::<u8>::new();
         let encoder = super::ProtobufEncoder::new();
         let res = encoder.encode(&mf, &mut writer);
@@ -74,4 +78,30 @@ mod tests {
                        240, 63];
         assert_eq!(ans, writer);
     }
+
+    #[test]
+    fn test_protobuf_created_timestamp() {
+        use crate::histogram::{Histogram, HistogramOpts};
+        use crate::metrics::Collector;
+        use crate::proto::MetricFamily;
+        use protobuf::Message;
+
+        let histogram =
+            Histogram::with_opts(HistogramOpts::new("test_histogram", "test help")).unwrap();
+        let mf = histogram.collect();
+        let created = mf[0].get_metric()[0]
+            .get_histogram()
+            .get_created_timestamp();
+        assert!(created.get_seconds() > 0);
+
+        let bytes = mf[0].write_to_bytes().unwrap();
+        let decoded = MetricFamily::parse_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, mf[0]);
+        assert_eq!(
+            decoded.get_metric()[0]
+                .get_histogram()
+                .get_created_timestamp(),
+            created
+        );
+    }
 }
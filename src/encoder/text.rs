@@ -47,7 +47,12 @@ impl Encoder for TextEncoder {
 
             // Write `# TYPE` header.
             let metric_type = mf.get_field_type();
-            let lowercase_type = format!("{:?}", metric_type).to_lowercase();
+            let lowercase_type = match metric_type {
+                // The text format has no gauge histogram type, expose it as a
+                // plain histogram instead.
+                MetricType::GAUGE_HISTOGRAM => "histogram".to_owned(),
+                _ => format!("{:?}", metric_type).to_lowercase(),
+            };
             writer.write_all(b"# TYPE ")?;
             writer.write_all(name.as_bytes())?;
             writer.write_all(b" ")?;
@@ -62,7 +67,7 @@ impl Encoder for TextEncoder {
                     MetricType::GAUGE => {
                         write_sample(writer, name, None, m, None, m.get_gauge().get_value())?;
                     }
-                    MetricType::HISTOGRAM => {
+                    MetricType::HISTOGRAM | MetricType::GAUGE_HISTOGRAM => {
                         let h = m.get_histogram();
 
                         let mut inf_seen = false;
@@ -183,7 +188,7 @@ fn write_sample(
 /// written. Otherwise, the label pairs are written, escaped as required by the
 /// text format, and enclosed in '{...}'. The function returns the number of
 /// bytes written and any error encountered.
-fn label_pairs_to_text(
+pub(super) fn label_pairs_to_text(
     pairs: &[proto::LabelPair],
     additional_label: Option<(&str, &str)>,
     writer: &mut dyn Write,
@@ -221,7 +226,7 @@ fn label_pairs_to_text(
 ///
 /// Implementation adapted from
 /// https://lise-henry.github.io/articles/optimising_strings.html
-fn escape_string(v: &str, include_double_quote: bool) -> Cow<'_, str> {
+pub(super) fn escape_string(v: &str, include_double_quote: bool) -> Cow<'_, str> {
     // Regex compilation is expensive. Use `lazy_static` to compile the regexes
     // once per process lifetime and not once per function invocation.
     lazy_static! {
@@ -0,0 +1,315 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+/*!
+
+Adapters to instrument asynchronous code.
+
+A [`HistogramTimer`](crate::HistogramTimer) held across `.await` points keeps
+timing while the task is suspended, but has to be carried around by hand.
+The adapters of [`FutureExt`] wrap a [`Future`] instead, and track it from its
+first poll, or from its creation, until it completes or is dropped.
+
+*/
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::atomic64::Atomic;
+use crate::gauge::GenericGauge;
+use crate::histogram::{Histogram, Instant};
+
+/// An extension trait for [`Future`]s that records metrics about them.
+///
+/// # Examples
+///
+/// ```
+/// use prometheus::future::FutureExt;
+/// use prometheus::{Histogram, HistogramOpts, IntGauge};
+///
+/// let latency = Histogram::with_opts(HistogramOpts::new("latency", "help")).unwrap();
+/// let in_flight = IntGauge::new("in_flight", "help").unwrap();
+///
+/// let request = async { 42 }
+///     .observe_duration(&latency)
+///     .track_in_flight(&in_flight);
+/// # drop(request);
+/// ```
+pub trait FutureExt: Future + Sized {
+    /// Observe the time from the first poll of the future until it completes
+    /// into `histogram`, in seconds.
+    ///
+    /// The duration is also observed when the future is dropped before it
+    /// completes, unless [`ObserveDuration::discard_cancelled`] is used.
+    fn observe_duration(self, histogram: &Histogram) -> ObserveDuration<Self> {
+        ObserveDuration {
+            future: self,
+            histogram: histogram.clone(),
+            start: None,
+            observe_cancelled: true,
+            done: false,
+        }
+    }
+
+    /// Increase `gauge` by 1 while the future is running, i.e. from its first
+    /// poll until it completes or is dropped.
+    fn track_in_flight<P: Atomic>(self, gauge: &GenericGauge<P>) -> TrackInFlight<Self, P> {
+        TrackInFlight {
+            future: self,
+            gauge: gauge.clone(),
+            tracking: false,
+            done: false,
+        }
+    }
+}
+
+impl<F: Future> FutureExt for F {}
+
+/// A [`Future`] that observes its duration into a [`Histogram`], created by
+/// [`FutureExt::observe_duration`].
+#[must_use = "futures do nothing unless polled"]
+pub struct ObserveDuration<F> {
+    future: F,
+    histogram: Histogram,
+    start: Option<Instant>,
+    observe_cancelled: bool,
+    done: bool,
+}
+
+impl<F> ObserveDuration<F> {
+    /// Start timing now instead of at the first poll, e.g. to include the
+    /// time a spawned task waits to be scheduled.
+    pub fn from_creation(mut self) -> Self {
+        self.start = Some(Instant::now());
+        self
+    }
+
+    /// Do not observe the duration if the future is dropped before it
+    /// completes, e.g. because the request was cancelled.
+    pub fn discard_cancelled(mut self) -> Self {
+        self.observe_cancelled = false;
+        self
+    }
+}
+
+impl<F: Future> Future for ObserveDuration<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        // Safety: `future` is pinned structurally, it is never moved out of
+        // `self`, and no other field is pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        if this.start.is_none() {
+            this.start = Some(Instant::now());
+        }
+
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        match future.poll(cx) {
+            Poll::Ready(output) => {
+                this.done = true;
+                if let Some(ref start) = this.start {
+                    this.histogram.observe(start.elapsed_sec());
+                }
+                Poll::Ready(output)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<F> Drop for ObserveDuration<F> {
+    fn drop(&mut self) {
+        if self.done || !self.observe_cancelled {
+            return;
+        }
+        // Futures dropped before their first poll did not start.
+        if let Some(ref start) = self.start {
+            self.histogram.observe(start.elapsed_sec());
+        }
+    }
+}
+
+impl<F> fmt::Debug for ObserveDuration<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObserveDuration")
+            .field("histogram", &self.histogram)
+            .field("start", &self.start)
+            .field("observe_cancelled", &self.observe_cancelled)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+/// A [`Future`] that counts itself in a gauge while it runs, created by
+/// [`FutureExt::track_in_flight`].
+#[must_use = "futures do nothing unless polled"]
+pub struct TrackInFlight<F, P: Atomic> {
+    future: F,
+    gauge: GenericGauge<P>,
+    tracking: bool,
+    done: bool,
+}
+
+impl<F, P: Atomic> TrackInFlight<F, P> {
+    /// Count the future as in flight from now instead of from its first poll.
+    pub fn from_creation(mut self) -> Self {
+        self.start_tracking();
+        self
+    }
+
+    fn start_tracking(&mut self) {
+        if !self.tracking && !self.done {
+            self.gauge.inc();
+            self.tracking = true;
+        }
+    }
+
+    fn stop_tracking(&mut self) {
+        if self.tracking {
+            self.gauge.dec();
+            self.tracking = false;
+        }
+    }
+}
+
+impl<F: Future, P: Atomic> Future for TrackInFlight<F, P> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        // Safety: `future` is pinned structurally, it is never moved out of
+        // `self`, and no other field is pinned.
+        let this = unsafe { self.get_unchecked_mut() };
+        this.start_tracking();
+
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+        match future.poll(cx) {
+            Poll::Ready(output) => {
+                this.stop_tracking();
+                this.done = true;
+                Poll::Ready(output)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<F, P: Atomic> Drop for TrackInFlight<F, P> {
+    fn drop(&mut self) {
+        self.stop_tracking();
+    }
+}
+
+impl<F, P: Atomic> fmt::Debug for TrackInFlight<F, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackInFlight")
+            .field("gauge", &self.gauge.fq_name())
+            .field("tracking", &self.tracking)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+
+    use super::*;
+    use crate::gauge::IntGauge;
+    use crate::histogram::HistogramOpts;
+
+    // A future that is pending on its first poll.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = u32;
+
+        fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<u32> {
+            if self.0 {
+                Poll::Ready(42)
+            } else {
+                self.0 = true;
+                Poll::Pending
+            }
+        }
+    }
+
+    fn noop_waker() -> Waker {
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) }
+    }
+
+    fn poll<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+        let waker = noop_waker();
+        Pin::new(future).poll(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn test_observe_duration() {
+        let histogram = Histogram::with_opts(HistogramOpts::new("test", "test help")).unwrap();
+
+        let mut future = YieldOnce(false).observe_duration(&histogram);
+        assert!(poll(&mut future).is_pending());
+        assert_eq!(histogram.get_sample_count(), 0);
+        assert_eq!(poll(&mut future), Poll::Ready(42));
+        assert_eq!(histogram.get_sample_count(), 1);
+        drop(future);
+        assert_eq!(histogram.get_sample_count(), 1);
+
+        // Not started, nothing to observe.
+        drop(YieldOnce(false).observe_duration(&histogram));
+        assert_eq!(histogram.get_sample_count(), 1);
+
+        // Cancelled.
+        let mut future = YieldOnce(false).observe_duration(&histogram);
+        assert!(poll(&mut future).is_pending());
+        drop(future);
+        assert_eq!(histogram.get_sample_count(), 2);
+
+        let mut future = YieldOnce(false)
+            .observe_duration(&histogram)
+            .discard_cancelled();
+        assert!(poll(&mut future).is_pending());
+        drop(future);
+        assert_eq!(histogram.get_sample_count(), 2);
+
+        drop(
+            YieldOnce(false)
+                .observe_duration(&histogram)
+                .from_creation(),
+        );
+        assert_eq!(histogram.get_sample_count(), 3);
+    }
+
+    #[test]
+    fn test_track_in_flight() {
+        let gauge = IntGauge::new("test", "test help").unwrap();
+
+        let mut future = YieldOnce(false).track_in_flight(&gauge);
+        assert_eq!(gauge.get(), 0);
+        assert!(poll(&mut future).is_pending());
+        assert_eq!(gauge.get(), 1);
+        assert_eq!(poll(&mut future), Poll::Ready(42));
+        assert_eq!(gauge.get(), 0);
+        drop(future);
+        assert_eq!(gauge.get(), 0);
+
+        // Cancelled.
+        let mut future = YieldOnce(false).track_in_flight(&gauge);
+        assert!(poll(&mut future).is_pending());
+        assert_eq!(gauge.get(), 1);
+        drop(future);
+        assert_eq!(gauge.get(), 0);
+
+        let future = YieldOnce(false).track_in_flight(&gauge).from_creation();
+        assert_eq!(gauge.get(), 1);
+        drop(future);
+        assert_eq!(gauge.get(), 0);
+    }
+}
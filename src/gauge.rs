@@ -1,14 +1,17 @@
 // Copyright 2014 The Prometheus Authors
 // Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::atomic64::{Atomic, AtomicF64, AtomicI64, AtomicU64, Number};
 use crate::desc::Desc;
 use crate::errors::Result;
-use crate::metrics::{Collector, Metric, Opts};
+use crate::metrics::{Collector, LocalMetric, Metric, Opts};
 use crate::proto;
 use crate::value::{Value, ValueType};
 use crate::vec::{MetricVec, MetricVecBuilder};
@@ -70,6 +73,34 @@ impl<P: Atomic> GenericGauge<P> {
         self.v.set(v);
     }
 
+    /// Set the gauge to a value measured at the given time, in milliseconds
+    /// since the UNIX epoch, e.g. when relaying values read from another
+    /// system.
+    ///
+    /// The timestamp is exported with the value until it is replaced or
+    /// cleared with [`set_timestamp_ms`](Self::set_timestamp_ms), including
+    /// after other updates.
+    #[inline]
+    pub fn set_with_timestamp(&self, v: P::T, timestamp_ms: i64) {
+        self.v.set(v);
+        self.v.set_timestamp_ms(timestamp_ms);
+    }
+
+    /// Set the time at which the current value was measured, in milliseconds
+    /// since the UNIX epoch. 0 clears the timestamp, so that scrapers assume
+    /// the value is current.
+    #[inline]
+    pub fn set_timestamp_ms(&self, timestamp_ms: i64) {
+        self.v.set_timestamp_ms(timestamp_ms);
+    }
+
+    /// Return the explicit timestamp of the gauge, in milliseconds since the
+    /// UNIX epoch, or 0 if it has none.
+    #[inline]
+    pub fn get_timestamp_ms(&self) -> i64 {
+        self.v.get_timestamp_ms()
+    }
+
     /// Increase the gauge by 1.
     #[inline]
     pub fn inc(&self) {
@@ -96,11 +127,40 @@ impl<P: Atomic> GenericGauge<P> {
         self.v.dec_by(v);
     }
 
+    /// Set the gauge to the given value if it is greater than the current
+    /// one, e.g. to track a high-water mark.
+    #[inline]
+    pub fn set_max(&self, v: P::T) {
+        self.v.set_max(v);
+    }
+
+    /// Set the gauge to the given value if it is less than the current one,
+    /// e.g. to track a low-water mark.
+    #[inline]
+    pub fn set_min(&self, v: P::T) {
+        self.v.set_min(v);
+    }
+
+    /// Set the gauge to the current Unix time in seconds. Integer gauges
+    /// truncate it to whole seconds.
+    pub fn set_to_current_time(&self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let secs = now.as_secs() as f64 + f64::from(now.subsec_nanos()) / 1e9;
+        self.v.set(P::T::from_f64(secs));
+    }
+
     /// Return the gauge value.
     #[inline]
     pub fn get(&self) -> P::T {
         self.v.get()
     }
+
+    /// Return a [`GenericLocalGauge`] for single thread usage.
+    pub fn local(&self) -> GenericLocalGauge<P> {
+        GenericLocalGauge::new(self.clone())
+    }
 }
 
 impl<P: Atomic> Collector for GenericGauge<P> {
@@ -175,6 +235,187 @@ impl<P: Atomic> GenericGaugeVec<P> {
 
         Ok(metric_vec as Self)
     }
+
+    /// Return a [`GenericLocalGaugeVec`] for single thread usage.
+    pub fn local(&self) -> GenericLocalGaugeVec<P> {
+        GenericLocalGaugeVec::new(self.clone())
+    }
+}
+
+/// The underlying implementation for [`LocalGauge`]
+/// and [`LocalIntGauge`].
+///
+/// Only relative changes are buffered, setting an absolute value must go
+/// through the shared [`GenericGauge`].
+#[derive(Debug)]
+pub struct GenericLocalGauge<P: Atomic> {
+    gauge: GenericGauge<P>,
+    val: RefCell<P::T>,
+}
+
+/// An unsync [`Gauge`].
+///
+/// For auto_flush::AFLocalGauge to use to make type inference possible
+pub trait GaugeWithValueType {
+    ///the exact type which implements Atomic
+    type ValueType: Atomic;
+}
+
+impl<P: Atomic> GaugeWithValueType for GenericLocalGauge<P> {
+    type ValueType = P;
+}
+
+/// An unsync [`Gauge`].
+pub type LocalGauge = GenericLocalGauge<AtomicF64>;
+
+/// The integer version of [`LocalGauge`]. Provides better performance
+/// if metric values are all integers.
+pub type LocalIntGauge = GenericLocalGauge<AtomicI64>;
+
+impl<P: Atomic> GenericLocalGauge<P> {
+    fn new(gauge: GenericGauge<P>) -> Self {
+        Self {
+            gauge,
+            val: RefCell::new(P::T::from_i64(0)),
+        }
+    }
+
+    /// Increase the local gauge by 1.
+    #[inline]
+    pub fn inc(&self) {
+        *self.val.borrow_mut() += P::T::from_i64(1);
+    }
+
+    /// Decrease the local gauge by 1.
+    #[inline]
+    pub fn dec(&self) {
+        *self.val.borrow_mut() -= P::T::from_i64(1);
+    }
+
+    /// Add the given value to the local gauge. (The value can be
+    /// negative, resulting in a decrement of the gauge.)
+    #[inline]
+    pub fn add(&self, v: P::T) {
+        *self.val.borrow_mut() += v;
+    }
+
+    /// Subtract the given value from the local gauge. (The value can be
+    /// negative, resulting in an increment of the gauge.)
+    #[inline]
+    pub fn sub(&self, v: P::T) {
+        *self.val.borrow_mut() -= v;
+    }
+
+    /// Return the delta accumulated since the last flush.
+    #[inline]
+    pub fn get(&self) -> P::T {
+        *self.val.borrow()
+    }
+
+    /// Discard the delta accumulated since the last flush.
+    #[inline]
+    pub fn reset(&self) {
+        *self.val.borrow_mut() = P::T::from_i64(0);
+    }
+
+    /// Flush the local metrics to the [`Gauge`].
+    #[inline]
+    pub fn flush(&self) {
+        if *self.val.borrow() == P::T::from_i64(0) {
+            return;
+        }
+        self.gauge.add(*self.val.borrow());
+        *self.val.borrow_mut() = P::T::from_i64(0);
+    }
+}
+
+impl<P: Atomic> LocalMetric for GenericLocalGauge<P> {
+    /// Flush the local metrics to the [`Gauge`].
+    #[inline]
+    fn flush(&self) {
+        GenericLocalGauge::flush(self);
+    }
+}
+
+impl<P: Atomic> Clone for GenericLocalGauge<P> {
+    fn clone(&self) -> Self {
+        Self::new(self.gauge.clone())
+    }
+}
+
+impl<P: Atomic> Drop for GenericLocalGauge<P> {
+    fn drop(&mut self) {
+        self.flush()
+    }
+}
+
+/// The underlying implementation for [`LocalGaugeVec`]
+/// and [`LocalIntGaugeVec`].
+pub struct GenericLocalGaugeVec<P: Atomic> {
+    vec: GenericGaugeVec<P>,
+    local: HashMap<u64, GenericLocalGauge<P>>,
+}
+
+impl<P: Atomic> std::fmt::Debug for GenericLocalGaugeVec<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GenericLocalGaugeVec ({} locals)",
+            self.local.keys().len()
+        )
+    }
+}
+
+/// An unsync [`GaugeVec`].
+pub type LocalGaugeVec = GenericLocalGaugeVec<AtomicF64>;
+
+/// The integer version of [`LocalGaugeVec`].
+/// Provides better performance if metric values are all integers.
+pub type LocalIntGaugeVec = GenericLocalGaugeVec<AtomicI64>;
+
+impl<P: Atomic> GenericLocalGaugeVec<P> {
+    fn new(vec: GenericGaugeVec<P>) -> Self {
+        let local = HashMap::with_capacity(vec.v.children.read().len());
+        Self { vec, local }
+    }
+
+    /// Get a [`GenericLocalGauge`] by label values.
+    /// See more [MetricVec::with_label_values].
+    pub fn with_label_values<'a>(&'a mut self, vals: &[&str]) -> &'a mut GenericLocalGauge<P> {
+        let hash = self.vec.v.hash_label_values(vals).unwrap();
+        let vec = &self.vec;
+        self.local
+            .entry(hash)
+            .or_insert_with(|| vec.with_label_values(vals).local())
+    }
+
+    /// Remove a [`GenericLocalGauge`] by label values.
+    /// See more [MetricVec::remove_label_values].
+    pub fn remove_label_values(&mut self, vals: &[&str]) -> Result<()> {
+        let hash = self.vec.v.hash_label_values(vals)?;
+        self.local.remove(&hash);
+        self.vec.v.delete_label_values(vals)
+    }
+
+    /// Flush the local metrics to the [`GaugeVec`] metric.
+    pub fn flush(&self) {
+        for g in self.local.values() {
+            g.flush();
+        }
+    }
+}
+
+impl<P: Atomic> LocalMetric for GenericLocalGaugeVec<P> {
+    /// Flush the local metrics to the [`GaugeVec`] metric.
+    fn flush(&self) {
+        GenericLocalGaugeVec::flush(self);
+    }
+}
+
+impl<P: Atomic> Clone for GenericLocalGaugeVec<P> {
+    fn clone(&self) -> Self {
+        Self::new(self.vec.clone())
+    }
 }
 
 /// A [`GenericGauge`] wrapper that deletes its labels from the vec when it is dropped
@@ -307,6 +548,135 @@ mod tests {
         assert_eq!(m.get_gauge().get_value() as u64, 42);
     }
 
+    #[test]
+    fn test_local_gauge() {
+        let gauge = Gauge::new("gauge", "gauge helper").unwrap();
+        let local_gauge1 = gauge.local();
+        let local_gauge2 = gauge.local();
+
+        gauge.set(10.0);
+        local_gauge1.inc();
+        local_gauge1.add(4.0);
+        local_gauge2.dec();
+        local_gauge2.sub(2.0);
+        assert_eq!(local_gauge1.get() as i64, 5);
+        assert_eq!(local_gauge2.get() as i64, -3);
+        assert_eq!(gauge.get() as i64, 10);
+        local_gauge1.flush();
+        assert_eq!(local_gauge1.get() as i64, 0);
+        assert_eq!(gauge.get() as i64, 15);
+        local_gauge2.flush();
+        assert_eq!(gauge.get() as i64, 12);
+
+        local_gauge1.add(7.0);
+        local_gauge1.reset();
+        local_gauge1.flush();
+        assert_eq!(gauge.get() as i64, 12);
+    }
+
+    #[test]
+    fn test_int_local_gauge() {
+        let gauge = IntGauge::new("foo", "bar").unwrap();
+        let local_gauge = gauge.local();
+
+        local_gauge.inc();
+        local_gauge.sub(3);
+        assert_eq!(local_gauge.get(), -2);
+        assert_eq!(gauge.get(), 0);
+
+        local_gauge.flush();
+        assert_eq!(local_gauge.get(), 0);
+        assert_eq!(gauge.get(), -2);
+
+        local_gauge.add(5);
+        local_gauge.flush();
+        assert_eq!(gauge.get(), 3);
+    }
+
+    #[test]
+    fn test_int_gauge_vec_local() {
+        let vec = IntGaugeVec::new(Opts::new("foo", "bar"), &["l1", "l2"]).unwrap();
+        let mut local_vec_1 = vec.local();
+        let mut local_vec_2 = local_vec_1.clone();
+        assert!(local_vec_1.remove_label_values(&["v1", "v2"]).is_err());
+
+        local_vec_1.with_label_values(&["v1", "v2"]).add(23);
+        local_vec_2.with_label_values(&["v1", "v2"]).sub(3);
+        assert_eq!(local_vec_1.with_label_values(&["v1", "v2"]).get(), 23);
+        assert_eq!(vec.with_label_values(&["v1", "v2"]).get(), 0);
+
+        local_vec_1.flush();
+        assert_eq!(local_vec_1.with_label_values(&["v1", "v2"]).get(), 0);
+        assert_eq!(vec.with_label_values(&["v1", "v2"]).get(), 23);
+
+        local_vec_2.flush();
+        assert_eq!(vec.with_label_values(&["v1", "v2"]).get(), 20);
+
+        // When calling `remove_label_values`, it is "flushed" immediately.
+        local_vec_1.with_label_values(&["v1", "v2"]).dec();
+        assert!(local_vec_1.remove_label_values(&["v1", "v2"]).is_ok());
+        assert_eq!(local_vec_1.with_label_values(&["v1", "v2"]).get(), 0);
+        assert_eq!(vec.with_label_values(&["v1", "v2"]).get(), 0);
+        assert!(local_vec_1.remove_label_values(&["v1"]).is_err());
+    }
+
+    #[test]
+    fn test_gauge_watermarks() {
+        let gauge = IntGauge::new("test_int_gauge", "test help").unwrap();
+        gauge.set_max(10);
+        assert_eq!(gauge.get(), 10);
+        gauge.set_max(5);
+        assert_eq!(gauge.get(), 10);
+        gauge.set_min(-2);
+        assert_eq!(gauge.get(), -2);
+        gauge.set_min(3);
+        assert_eq!(gauge.get(), -2);
+
+        let gauge = Gauge::new("test_gauge", "test help").unwrap();
+        gauge.set_max(1.5);
+        gauge.set_max(0.5);
+        assert_eq!(gauge.get(), 1.5);
+
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        gauge.set_to_current_time();
+        let int_gauge = UIntGauge::new("test_uint_gauge", "test help").unwrap();
+        int_gauge.set_to_current_time();
+        assert!(gauge.get() >= before as f64);
+        assert!(int_gauge.get() >= before);
+        assert!(int_gauge.get() <= before + 60);
+    }
+
+    #[test]
+    fn test_gauge_with_timestamp() {
+        use crate::encoder::{Encoder, TextEncoder};
+        use crate::registry::Registry;
+
+        let vec = GaugeVec::new(Opts::new("test_gauge", "test help"), &["l"]).unwrap();
+        let r = Registry::new();
+        r.register(Box::new(vec.clone())).unwrap();
+        let gauge = vec.with_label_values(&["a"]);
+        gauge.set_with_timestamp(2.0, 1_500_000_000_000);
+        vec.with_label_values(&["b"]).set(1.0);
+        assert_eq!(gauge.get_timestamp_ms(), 1_500_000_000_000);
+
+        // The timestamp survives other updates until cleared.
+        gauge.inc();
+        let mut writer = Vec::<u8>::new();
+        TextEncoder::new().encode(&r.gather(), &mut writer).unwrap();
+        let ans = r##"# HELP test_gauge test help
+# TYPE test_gauge gauge
+test_gauge{l="a"} 3 1500000000000
+test_gauge{l="b"} 1
+"##;
+        assert_eq!(ans, String::from_utf8(writer).unwrap());
+
+        gauge.set_timestamp_ms(0);
+        assert_eq!(gauge.metric().get_timestamp_ms(), 0);
+    }
+
     #[test]
     fn test_gauge_vec_with_labels() {
         let vec = GaugeVec::new(
@@ -0,0 +1,363 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::sync::Arc;
+
+use crate::atomic64::{Atomic, AtomicF64, AtomicI64};
+use crate::desc::{Desc, Describer};
+use crate::errors::Result;
+use crate::histogram::{check_and_adjust_buckets, check_bucket_label, HistogramOpts};
+use crate::metrics::{Collector, Metric};
+use crate::proto;
+use crate::value::make_label_pairs;
+use crate::vec::{MetricVec, MetricVecBuilder};
+
+#[derive(Debug)]
+pub struct GaugeHistogramCore {
+    desc: Desc,
+    label_pairs: Vec<proto::LabelPair>,
+
+    sum: AtomicF64,
+
+    upper_bounds: Vec<f64>,
+    // One more than `upper_bounds`, the last one counts the +Inf bucket.
+    counts: Vec<AtomicI64>,
+}
+
+impl GaugeHistogramCore {
+    pub fn new(opts: &HistogramOpts, label_values: &[&str]) -> Result<GaugeHistogramCore> {
+        let desc = opts.describe()?;
+
+        for name in &desc.variable_labels {
+            check_bucket_label(name)?;
+        }
+        for pair in &desc.const_label_pairs {
+            check_bucket_label(pair.get_name())?;
+        }
+
+        let label_pairs = make_label_pairs(&desc, label_values)?;
+
+        let buckets = check_and_adjust_buckets(opts.buckets.clone())?;
+
+        let mut counts = Vec::new();
+        for _ in 0..=buckets.len() {
+            counts.push(AtomicI64::new(0));
+        }
+
+        Ok(GaugeHistogramCore {
+            desc,
+            label_pairs,
+            sum: AtomicF64::new(0.0),
+            upper_bounds: buckets,
+            counts,
+        })
+    }
+
+    fn bucket_index(&self, v: f64) -> usize {
+        self.upper_bounds
+            .iter()
+            .position(|f| v <= *f)
+            .unwrap_or(self.upper_bounds.len())
+    }
+
+    pub fn inc(&self, v: f64) {
+        self.counts[self.bucket_index(v)].inc_by(1);
+        self.sum.inc_by(v);
+    }
+
+    pub fn dec(&self, v: f64) {
+        self.counts[self.bucket_index(v)].dec_by(1);
+        self.sum.dec_by(v);
+    }
+
+    pub fn set(&self, values: &[f64]) {
+        let mut counts = vec![0; self.counts.len()];
+        let mut sum = 0.0;
+        for v in values {
+            counts[self.bucket_index(*v)] += 1;
+            sum += *v;
+        }
+
+        for (count, v) in self.counts.iter().zip(counts) {
+            count.set(v);
+        }
+        self.sum.set(sum);
+    }
+
+    pub fn proto(&self) -> proto::Histogram {
+        let mut h = proto::Histogram::default();
+        h.set_sample_sum(self.sum.get());
+        h.set_sample_count(non_negative(self.sample_count()));
+
+        let mut count = 0;
+        let mut buckets = Vec::with_capacity(self.upper_bounds.len());
+        for (i, upper_bound) in self.upper_bounds.iter().enumerate() {
+            count += self.counts[i].get();
+            let mut b = proto::Bucket::default();
+            b.set_cumulative_count(non_negative(count));
+            b.set_upper_bound(*upper_bound);
+            buckets.push(b);
+        }
+        h.set_bucket(from_vec!(buckets));
+
+        h
+    }
+
+    fn sample_sum(&self) -> f64 {
+        self.sum.get()
+    }
+
+    fn sample_count(&self) -> i64 {
+        self.counts.iter().map(Atomic::get).sum()
+    }
+}
+
+// Concurrent `inc` and `dec` calls may briefly leave a bucket below zero,
+// which is not representable on the wire.
+#[inline]
+fn non_negative(count: i64) -> u64 {
+    if count < 0 {
+        0
+    } else {
+        count as u64
+    }
+}
+
+/// A [`Metric`] counts the current distribution of some values in configurable
+/// buckets. Unlike a [`Histogram`](crate::Histogram), whose buckets only ever
+/// go up, values can be removed again, so the bucket counts of a
+/// [`GaugeHistogram`] can go up and down, e.g. the number of items currently
+/// in a queue partitioned by their age.
+///
+/// It is exposed with the `gaugehistogram` type by the
+/// [`OpenMetricsEncoder`](crate::OpenMetricsEncoder), and as a plain histogram
+/// by the [`TextEncoder`](crate::TextEncoder).
+#[derive(Clone, Debug)]
+pub struct GaugeHistogram {
+    core: Arc<GaugeHistogramCore>,
+}
+
+impl GaugeHistogram {
+    /// `with_opts` creates a [`GaugeHistogram`] with the `opts` options.
+    pub fn with_opts(opts: HistogramOpts) -> Result<GaugeHistogram> {
+        GaugeHistogram::with_opts_and_label_values(&opts, &[])
+    }
+
+    fn with_opts_and_label_values(
+        opts: &HistogramOpts,
+        label_values: &[&str],
+    ) -> Result<GaugeHistogram> {
+        let core = GaugeHistogramCore::new(opts, label_values)?;
+
+        Ok(GaugeHistogram {
+            core: Arc::new(core),
+        })
+    }
+}
+
+impl GaugeHistogram {
+    /// Add a value to the [`GaugeHistogram`], increasing the bucket it falls
+    /// into by 1.
+    pub fn inc(&self, v: f64) {
+        self.core.inc(v)
+    }
+
+    /// Remove a value previously added by [`inc`](GaugeHistogram::inc),
+    /// decreasing the bucket it falls into by 1.
+    pub fn dec(&self, v: f64) {
+        self.core.dec(v)
+    }
+
+    /// Replace the current distribution with the given values.
+    ///
+    /// Buckets are updated one by one, so a concurrent collection may observe
+    /// a mix of the old and the new distribution.
+    pub fn set(&self, values: &[f64]) {
+        self.core.set(values)
+    }
+
+    /// Remove all values from the [`GaugeHistogram`].
+    pub fn reset(&self) {
+        self.core.set(&[])
+    }
+
+    /// Return the sum of all current values.
+    pub fn get_sample_sum(&self) -> f64 {
+        self.core.sample_sum()
+    }
+
+    /// Return the number of current values.
+    pub fn get_sample_count(&self) -> i64 {
+        self.core.sample_count()
+    }
+}
+
+impl Metric for GaugeHistogram {
+    fn metric(&self) -> proto::Metric {
+        let mut m = proto::Metric::default();
+        m.set_label(from_vec!(self.core.label_pairs.clone()));
+
+        let h = self.core.proto();
+        m.set_histogram(h);
+
+        m
+    }
+}
+
+impl Collector for GaugeHistogram {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![&self.core.desc]
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        let mut m = proto::MetricFamily::default();
+        m.set_name(self.core.desc.fq_name.clone());
+        m.set_help(self.core.desc.help.clone());
+        m.set_field_type(proto::MetricType::GAUGE_HISTOGRAM);
+        m.set_metric(from_vec!(vec![self.metric()]));
+
+        vec![m]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GaugeHistogramVecBuilder {}
+
+impl MetricVecBuilder for GaugeHistogramVecBuilder {
+    type M = GaugeHistogram;
+    type P = HistogramOpts;
+
+    fn build(&self, opts: &HistogramOpts, vals: &[&str]) -> Result<GaugeHistogram> {
+        GaugeHistogram::with_opts_and_label_values(opts, vals)
+    }
+}
+
+/// A [`Collector`] that bundles a set of [`GaugeHistogram`]s that all share the
+/// same [`Desc`], but have different values for their variable labels. This is
+/// used if you want to track the same distribution partitioned by various
+/// dimensions (e.g. queued items by age, partitioned by queue).
+pub type GaugeHistogramVec = MetricVec<GaugeHistogramVecBuilder>;
+
+impl GaugeHistogramVec {
+    /// Create a new [`GaugeHistogramVec`] based on the provided
+    /// [`HistogramOpts`] and partitioned by the given label names. At least
+    /// one label name must be provided.
+    pub fn new(opts: HistogramOpts, label_names: &[&str]) -> Result<GaugeHistogramVec> {
+        let variable_names = label_names.iter().map(|s| (*s).to_owned()).collect();
+        let opts = opts.variable_labels(variable_names);
+        let metric_vec = MetricVec::create(
+            proto::MetricType::GAUGE_HISTOGRAM,
+            GaugeHistogramVecBuilder {},
+            opts,
+        )?;
+
+        Ok(metric_vec as GaugeHistogramVec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::{Encoder, OpenMetricsEncoder, TextEncoder};
+
+    #[test]
+    fn test_gauge_histogram() {
+        let opts = HistogramOpts::new("test_gauge_histogram", "test help")
+            .const_label("a", "1")
+            .buckets(vec![1.0, 2.0]);
+        let gh = GaugeHistogram::with_opts(opts).unwrap();
+
+        gh.inc(0.5);
+        gh.inc(1.5);
+        gh.inc(3.0);
+        assert_eq!(gh.get_sample_count(), 3);
+        assert!((gh.get_sample_sum() - 5.0).abs() < f64::EPSILON);
+
+        gh.dec(1.5);
+        assert_eq!(gh.get_sample_count(), 2);
+        assert!((gh.get_sample_sum() - 3.5).abs() < f64::EPSILON);
+
+        let mut mfs = gh.collect();
+        assert_eq!(mfs.len(), 1);
+        let mf = mfs.pop().unwrap();
+        assert_eq!(mf.get_field_type(), proto::MetricType::GAUGE_HISTOGRAM);
+        let h = mf.get_metric()[0].get_histogram();
+        assert_eq!(h.get_sample_count(), 2);
+        let buckets: Vec<_> = h
+            .get_bucket()
+            .iter()
+            .map(|b| b.get_cumulative_count())
+            .collect();
+        assert_eq!(buckets, vec![1, 1]);
+
+        gh.set(&[2.0, 2.0, 0.0]);
+        assert_eq!(gh.get_sample_count(), 3);
+        let m = gh.metric();
+        let h = m.get_histogram();
+        let buckets: Vec<_> = h
+            .get_bucket()
+            .iter()
+            .map(|b| b.get_cumulative_count())
+            .collect();
+        assert_eq!(buckets, vec![1, 3]);
+
+        gh.reset();
+        assert_eq!(gh.get_sample_count(), 0);
+        assert!(gh.get_sample_sum().abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_gauge_histogram_encoding() {
+        let opts = HistogramOpts::new("test_gauge_histogram", "test help").buckets(vec![1.0]);
+        let gh = GaugeHistogram::with_opts(opts).unwrap();
+        gh.inc(0.5);
+        gh.inc(2.0);
+
+        let mfs = gh.collect();
+        let mut writer = Vec::<u8>::new();
+        TextEncoder::new().encode(&mfs, &mut writer).unwrap();
+        let ans = r##"# HELP test_gauge_histogram test help
+# TYPE test_gauge_histogram histogram
+test_gauge_histogram_bucket{le="1"} 1
+test_gauge_histogram_bucket{le="+Inf"} 2
+test_gauge_histogram_sum 2.5
+test_gauge_histogram_count 2
+"##;
+        assert_eq!(ans, String::from_utf8(writer).unwrap());
+
+        let mut writer = Vec::<u8>::new();
+        OpenMetricsEncoder::new().encode(&mfs, &mut writer).unwrap();
+        let ans = r##"# TYPE test_gauge_histogram gaugehistogram
+# HELP test_gauge_histogram test help
+test_gauge_histogram_bucket{le="1"} 1
+test_gauge_histogram_bucket{le="+Inf"} 2
+test_gauge_histogram_gcount 2
+test_gauge_histogram_gsum 2.5
+# EOF
+"##;
+        assert_eq!(ans, String::from_utf8(writer).unwrap());
+    }
+
+    #[test]
+    fn test_gauge_histogram_vec() {
+        let vec = GaugeHistogramVec::new(
+            HistogramOpts::new("test_gauge_histogram_vec", "test help"),
+            &["l1"],
+        )
+        .unwrap();
+
+        vec.with_label_values(&["v1"]).inc(1.0);
+        vec.with_label_values(&["v1"]).inc(1.0);
+        vec.with_label_values(&["v1"]).dec(1.0);
+        assert_eq!(vec.with_label_values(&["v1"]).get_sample_count(), 1);
+
+        assert!(vec.remove_label_values(&["v1"]).is_ok());
+        assert!(vec.remove_label_values(&["v1"]).is_err());
+
+        let vec = GaugeHistogramVec::new(
+            HistogramOpts::new("test_gauge_histogram_vec", "test help"),
+            &["le"],
+        )
+        .unwrap();
+        assert!(vec.get_metric_with_label_values(&["1"]).is_err());
+    }
+}
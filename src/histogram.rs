@@ -5,12 +5,13 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::From;
 use std::sync::Arc;
-use std::time::{Duration, Instant as StdInstant};
+use std::time::Duration;
 
-use crate::atomic64::{Atomic, AtomicF64, AtomicU64};
+use crate::atomic64::{Atomic, AtomicF64, AtomicI64, AtomicU64};
+use crate::clock;
 use crate::desc::{Desc, Describer};
 use crate::errors::{Error, Result};
-use crate::metrics::{Collector, LocalMetric, Metric, Opts};
+use crate::metrics::{Collector, Created, LocalMetric, Metric, Opts};
 use crate::proto;
 use crate::value::make_label_pairs;
 use crate::vec::{MetricVec, MetricVecBuilder};
@@ -28,7 +29,7 @@ pub const DEFAULT_BUCKETS: &[f64; 11] = &[
 pub const BUCKET_LABEL: &str = "le";
 
 #[inline]
-fn check_bucket_label(label: &str) -> Result<()> {
+pub fn check_bucket_label(label: &str) -> Result<()> {
     if label == BUCKET_LABEL {
         return Err(Error::Msg(
             "`le` is not allowed as label name in histograms".to_owned(),
@@ -38,7 +39,7 @@ fn check_bucket_label(label: &str) -> Result<()> {
     Ok(())
 }
 
-fn check_and_adjust_buckets(mut buckets: Vec<f64>) -> Result<Vec<f64>> {
+pub fn check_and_adjust_buckets(mut buckets: Vec<f64>) -> Result<Vec<f64>> {
     if buckets.is_empty() {
         buckets = Vec::from(DEFAULT_BUCKETS as &'static [f64]);
     }
@@ -161,6 +162,10 @@ pub struct HistogramCore {
 
     upper_bounds: Vec<f64>,
     counts: Vec<AtomicU64>,
+
+    created: Created,
+    // Milliseconds since the UNIX epoch, 0 if the observations are current.
+    timestamp_ms: AtomicI64,
 }
 
 impl HistogramCore {
@@ -190,6 +195,8 @@ impl HistogramCore {
             count: AtomicU64::new(0),
             upper_bounds: buckets,
             counts,
+            created: Created::now(),
+            timestamp_ms: AtomicI64::new(0),
         })
     }
 
@@ -208,11 +215,26 @@ impl HistogramCore {
         self.sum.inc_by(v);
     }
 
+    pub fn reset(&self) {
+        for v in &self.counts {
+            v.set(0);
+        }
+
+        self.count.set(0);
+        self.sum.set(0.0);
+        self.created.reset();
+    }
+
     pub fn proto(&self) -> proto::Histogram {
         let mut h = proto::Histogram::default();
         h.set_sample_sum(self.sum.get());
         h.set_sample_count(self.count.get() as u64);
 
+        let (secs, nanos) = self.created.to_parts();
+        let created = h.mut_created_timestamp();
+        created.set_seconds(secs);
+        created.set_nanos(nanos);
+
         let mut count = 0;
         let mut buckets = Vec::with_capacity(self.upper_bounds.len());
         for (i, upper_bound) in self.upper_bounds.iter().enumerate() {
@@ -236,65 +258,36 @@ impl HistogramCore {
     }
 }
 
-// We have to wrap libc::timespec in order to implement std::fmt::Debug.
-#[cfg(all(feature = "nightly", target_os = "linux"))]
-pub struct Timespec(libc::timespec);
-
-#[cfg(all(feature = "nightly", target_os = "linux"))]
-impl std::fmt::Debug for Timespec {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Timespec {{ tv_sec: {}, tv_nsec: {} }}",
-            self.0.tv_sec, self.0.tv_nsec
-        )
-    }
-}
-
-#[derive(Debug)]
-pub enum Instant {
-    Monotonic(StdInstant),
-    #[cfg(all(feature = "nightly", target_os = "linux"))]
-    MonotonicCoarse(Timespec),
+/// A point in time of the installed [`Clock`](crate::clock::Clock).
+#[derive(Clone, Copy, Debug)]
+pub struct Instant {
+    at: Duration,
+    coarse: bool,
 }
 
 impl Instant {
     pub fn now() -> Instant {
-        Instant::Monotonic(StdInstant::now())
-    }
-
-    #[cfg(all(feature = "nightly", target_os = "linux"))]
-    pub fn now_coarse() -> Instant {
-        Instant::MonotonicCoarse(get_time_coarse())
+        Instant {
+            at: clock::now(),
+            coarse: false,
+        }
     }
 
-    #[cfg(all(feature = "nightly", not(target_os = "linux")))]
     pub fn now_coarse() -> Instant {
-        Instant::Monotonic(StdInstant::now())
+        Instant {
+            at: clock::now_coarse(),
+            coarse: true,
+        }
     }
 
     pub fn elapsed(&self) -> Duration {
-        match &*self {
-            Instant::Monotonic(i) => i.elapsed(),
-
-            // It is different from `Instant::Monotonic`, the resolution here is millisecond.
-            // The processors in an SMP system do not start all at exactly the same time
-            // and therefore the timer registers are typically running at an offset.
-            // Use millisecond resolution for ignoring the error.
-            // See more: https://linux.die.net/man/2/clock_gettime
-            #[cfg(all(feature = "nightly", target_os = "linux"))]
-            Instant::MonotonicCoarse(t) => {
-                let now = get_time_coarse();
-                let now_ms = now.0.tv_sec * MILLIS_PER_SEC + now.0.tv_nsec / NANOS_PER_MILLI;
-                let t_ms = t.0.tv_sec * MILLIS_PER_SEC + t.0.tv_nsec / NANOS_PER_MILLI;
-                let dur = now_ms - t_ms;
-                if dur >= 0 {
-                    Duration::from_millis(dur as u64)
-                } else {
-                    Duration::from_millis(0)
-                }
-            }
-        }
+        let now = if self.coarse {
+            clock::now_coarse()
+        } else {
+            clock::now()
+        };
+        // Instants taken before the clock was replaced may be ahead of it.
+        now.checked_sub(self.at).unwrap_or_default()
     }
 
     #[inline]
@@ -303,31 +296,6 @@ impl Instant {
     }
 }
 
-#[cfg(all(feature = "nightly", target_os = "linux"))]
-use self::coarse::*;
-
-#[cfg(all(feature = "nightly", target_os = "linux"))]
-mod coarse {
-    use crate::histogram::Timespec;
-    pub use libc::timespec;
-    use libc::{clock_gettime, CLOCK_MONOTONIC_COARSE};
-
-    pub const NANOS_PER_MILLI: i64 = 1_000_000;
-    pub const MILLIS_PER_SEC: i64 = 1_000;
-
-    pub fn get_time_coarse() -> Timespec {
-        let mut t = Timespec(timespec {
-            tv_sec: 0,
-            tv_nsec: 0,
-        });
-        assert_eq!(
-            unsafe { clock_gettime(CLOCK_MONOTONIC_COARSE, &mut t.0) },
-            0
-        );
-        t
-    }
-}
-
 /// Timer to measure and record the duration of an event.
 ///
 /// This timer can be stopped and observed at most once, either automatically (when it
@@ -353,7 +321,6 @@ impl HistogramTimer {
         }
     }
 
-    #[cfg(feature = "nightly")]
     fn new_coarse(histogram: Histogram) -> Self {
         HistogramTimer {
             histogram,
@@ -459,7 +426,10 @@ impl Histogram {
 
     /// Return a [`HistogramTimer`] to track a duration.
     /// It is faster but less precise.
-    #[cfg(feature = "nightly")]
+    ///
+    /// Without the `nightly` feature on Linux, the coarse time is refreshed
+    /// by a background thread every 200 milliseconds, which is then the
+    /// resolution of the timer, see [`SystemClock`](crate::clock::SystemClock).
     pub fn start_coarse_timer(&self) -> HistogramTimer {
         HistogramTimer::new_coarse(self.clone())
     }
@@ -476,8 +446,8 @@ impl Histogram {
         res
     }
 
-    /// Observe execution time of a closure, in second.
-    #[cfg(feature = "nightly")]
+    /// Observe execution time of a closure, in second, with the coarse clock
+    /// of [`start_coarse_timer`](Self::start_coarse_timer).
     pub fn observe_closure_duration_coarse<F, T>(&self, f: F) -> T
     where
         F: FnOnce() -> T,
@@ -503,6 +473,28 @@ impl Histogram {
     pub fn get_sample_count(&self) -> u64 {
         self.core.sample_count()
     }
+
+    /// Set the time at which the observations were last updated, in
+    /// milliseconds since the UNIX epoch, for histograms relaying data read
+    /// from another system. 0 clears the timestamp, so that scrapers assume
+    /// the observations are current.
+    pub fn set_timestamp_ms(&self, timestamp_ms: i64) {
+        self.core.timestamp_ms.set(timestamp_ms);
+    }
+
+    /// Return the explicit timestamp of the histogram, in milliseconds since
+    /// the UNIX epoch, or 0 if it has none.
+    pub fn get_timestamp_ms(&self) -> i64 {
+        self.core.timestamp_ms.get()
+    }
+
+    /// Restart the histogram, clearing all observations and resetting its
+    /// creation time to now.
+    ///
+    /// Observations made concurrently with a reset may be partially lost.
+    pub fn reset(&self) {
+        self.core.reset()
+    }
 }
 
 impl Metric for Histogram {
@@ -510,6 +502,11 @@ impl Metric for Histogram {
         let mut m = proto::Metric::default();
         m.set_label(from_vec!(self.core.label_pairs.clone()));
 
+        let timestamp_ms = self.core.timestamp_ms.get();
+        if timestamp_ms != 0 {
+            m.set_timestamp_ms(timestamp_ms);
+        }
+
         let h = self.core.proto();
         m.set_histogram(h);
 
@@ -639,6 +636,165 @@ pub fn exponential_buckets(start: f64, factor: f64, count: usize) -> Result<Vec<
     Ok(buckets)
 }
 
+/// Create `count` buckets, where the lowest bucket has an upper bound of `min`,
+/// the highest bucket has an upper bound of `max`, and the upper bounds in
+/// between grow by a constant factor. The final +Inf bucket is not counted and
+/// not included in the returned slice. The returned slice is meant to be used
+/// for the Buckets field of [`HistogramOpts`].
+///
+/// The function returns an error if `count` is zero, if `min` is zero or
+/// negative, or if `max` is less than or equal to `min`.
+pub fn exponential_buckets_range(min: f64, max: f64, count: usize) -> Result<Vec<f64>> {
+    if count < 1 {
+        return Err(Error::Msg(format!(
+            "exponential_buckets_range needs a positive count, count: {}",
+            count
+        )));
+    }
+    if min <= 0.0 {
+        return Err(Error::Msg(format!(
+            "exponential_buckets_range needs a positive min value, \
+             min: {}",
+            min
+        )));
+    }
+    if max <= min {
+        return Err(Error::Msg(format!(
+            "exponential_buckets_range needs a max value greater than min, \
+             min: {}, max: {}",
+            min, max
+        )));
+    }
+
+    if count == 1 {
+        return Ok(vec![min]);
+    }
+
+    // Compute every bound from `min` instead of multiplying repeatedly, so
+    // that the error does not accumulate, and pin the last one to `max`.
+    let factor = (max / min).powf(1.0 / (count - 1) as f64);
+    let mut buckets: Vec<_> = (0..count)
+        .map(|step| min * factor.powi(step as i32))
+        .collect();
+    buckets[count - 1] = max;
+
+    Ok(buckets)
+}
+
+/// Create log-linear buckets, i.e. `steps` linearly spaced buckets for every
+/// power of ten from `10^min_exp` up to `10^max_exp`. For example,
+/// `log_linear_buckets(-1, 1, 9)` returns `0.1, 0.2, ..., 0.9, 1, 2, ..., 9, 10`.
+/// The final +Inf bucket is not counted and not included in the returned
+/// slice. The returned slice is meant to be used for the Buckets field of
+/// [`HistogramOpts`].
+///
+/// The function returns an error if `steps` is zero or if `max_exp` is less
+/// than or equal to `min_exp`.
+pub fn log_linear_buckets(min_exp: i32, max_exp: i32, steps: usize) -> Result<Vec<f64>> {
+    if steps < 1 {
+        return Err(Error::Msg(format!(
+            "log_linear_buckets needs a positive steps, steps: {}",
+            steps
+        )));
+    }
+    if max_exp <= min_exp {
+        return Err(Error::Msg(format!(
+            "log_linear_buckets needs a max_exp greater than min_exp, \
+             min_exp: {}, max_exp: {}",
+            min_exp, max_exp
+        )));
+    }
+
+    // Scale by dividing for negative exponents, as 10^-n is not exactly
+    // representable while 10^n is.
+    let scale = |mantissa: f64, exp: i32| {
+        if exp >= 0 {
+            mantissa * 10f64.powi(exp)
+        } else {
+            mantissa / 10f64.powi(-exp)
+        }
+    };
+
+    let mut buckets = Vec::with_capacity((max_exp - min_exp) as usize * steps + 1);
+    for exp in min_exp..max_exp {
+        for step in 0..steps {
+            let mantissa = 1.0 + 9.0 * (step as f64) / (steps as f64);
+            buckets.push(scale(mantissa, exp));
+        }
+    }
+    buckets.push(scale(1.0, max_exp));
+
+    Ok(buckets)
+}
+
+/// A builder that merges several bucket sets, e.g. generated by
+/// [`linear_buckets`] and [`exponential_buckets`], into a single valid set of
+/// bucket upper bounds for [`HistogramOpts`].
+///
+/// Bounds are sorted and duplicates are removed, so the sets may overlap and
+/// may be given in any order.
+///
+/// # Examples
+///
+/// ```
+/// use prometheus::{exponential_buckets, linear_buckets, Buckets, HistogramOpts};
+///
+/// let buckets = Buckets::new()
+///     .merge(linear_buckets(0.0, 0.25, 5).unwrap())
+///     .merge(exponential_buckets(1.0, 2.0, 4).unwrap())
+///     .build()
+///     .unwrap();
+/// assert_eq!(buckets, vec![0.0, 0.25, 0.5, 0.75, 1.0, 2.0, 4.0, 8.0]);
+///
+/// let opts = HistogramOpts::new("example", "example help").buckets(buckets);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Buckets {
+    bounds: Vec<f64>,
+}
+
+impl Buckets {
+    /// Create an empty [`Buckets`] builder.
+    pub fn new() -> Buckets {
+        Buckets::default()
+    }
+
+    /// `merge` adds a set of bucket upper bounds.
+    pub fn merge<I: IntoIterator<Item = f64>>(mut self, bounds: I) -> Self {
+        self.bounds.extend(bounds);
+        self
+    }
+
+    /// `build` returns the merged bucket upper bounds, sorted in strictly
+    /// increasing order, with an explicit +Inf bound removed.
+    ///
+    /// The function returns an error if any of the bounds is NaN, or if no
+    /// bounds other than +Inf were added.
+    pub fn build(self) -> Result<Vec<f64>> {
+        let mut bounds = self.bounds;
+        if bounds.iter().any(|b| b.is_nan()) {
+            return Err(Error::Msg("Buckets must not contain NaN".to_owned()));
+        }
+
+        bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        bounds.dedup();
+        bounds.retain(|b| !(b.is_sign_positive() && b.is_infinite()));
+        if bounds.is_empty() {
+            return Err(Error::Msg(
+                "Buckets needs at least one bound other than +Inf".to_owned(),
+            ));
+        }
+
+        check_and_adjust_buckets(bounds)
+    }
+}
+
+impl From<Vec<f64>> for Buckets {
+    fn from(bounds: Vec<f64>) -> Buckets {
+        Buckets { bounds }
+    }
+}
+
 /// `duration_to_seconds` converts Duration to seconds.
 #[inline]
 pub fn duration_to_seconds(d: Duration) -> f64 {
@@ -690,7 +846,6 @@ impl LocalHistogramTimer {
         }
     }
 
-    #[cfg(feature = "nightly")]
     fn new_coarse(histogram: LocalHistogram) -> Self {
         Self {
             local: histogram,
@@ -832,7 +987,10 @@ impl LocalHistogram {
 
     /// Return a `LocalHistogramTimer` to track a duration.
     /// It is faster but less precise.
-    #[cfg(feature = "nightly")]
+    ///
+    /// Without the `nightly` feature on Linux, the coarse time is refreshed
+    /// by a background thread every 200 milliseconds, which is then the
+    /// resolution of the timer, see [`SystemClock`](crate::clock::SystemClock).
     pub fn start_coarse_timer(&self) -> LocalHistogramTimer {
         LocalHistogramTimer::new_coarse(self.clone())
     }
@@ -849,8 +1007,8 @@ impl LocalHistogram {
         res
     }
 
-    /// Observe execution time of a closure, in second.
-    #[cfg(feature = "nightly")]
+    /// Observe execution time of a closure, in second, with the coarse clock
+    /// of [`start_coarse_timer`](Self::start_coarse_timer).
     pub fn observe_closure_duration_coarse<F, T>(&self, f: F) -> T
     where
         F: FnOnce() -> T,
@@ -965,14 +1123,15 @@ mod tests {
         let histogram = Histogram::with_opts(opts).unwrap();
         histogram.observe(1.0);
 
+        let (clock, _guard) = clock::manual_for_test();
         let timer = histogram.start_timer();
-        thread::sleep(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
         timer.observe_duration();
 
         let timer = histogram.start_timer();
         let handler = thread::spawn(move || {
             let _timer = timer;
-            thread::sleep(Duration::from_millis(400));
+            clock.advance(Duration::from_millis(400));
         });
         assert!(handler.join().is_ok());
 
@@ -984,7 +1143,7 @@ mod tests {
         assert_eq!(m.get_label().len(), 2);
         let proto_histogram = m.get_histogram();
         assert_eq!(proto_histogram.get_sample_count(), 3);
-        assert!(proto_histogram.get_sample_sum() >= 1.5);
+        assert!((proto_histogram.get_sample_sum() - 1.5).abs() < f64::EPSILON);
         assert_eq!(proto_histogram.get_bucket().len(), DEFAULT_BUCKETS.len());
 
         let buckets = vec![1.0, 2.0, 3.0];
@@ -1003,24 +1162,58 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "nightly")]
+    fn test_histogram_reset() {
+        let opts = HistogramOpts::new("test_reset", "test help").buckets(vec![1.0]);
+        let histogram = Histogram::with_opts(opts).unwrap();
+        histogram.observe(0.5);
+        histogram.observe(2.0);
+
+        let created = |h: &Histogram| {
+            let ts = h.metric().get_histogram().get_created_timestamp().clone();
+            (ts.get_seconds(), ts.get_nanos())
+        };
+        let before = created(&histogram);
+        assert!(before.0 > 0);
+
+        thread::sleep(Duration::from_millis(1));
+        histogram.reset();
+        assert_eq!(histogram.get_sample_count(), 0);
+        assert_eq!(histogram.get_sample_sum(), 0.0);
+        let m = histogram.metric();
+        assert_eq!(m.get_histogram().get_bucket()[0].get_cumulative_count(), 0);
+        assert!(created(&histogram) > before);
+
+        assert_eq!(histogram.metric().get_timestamp_ms(), 0);
+        histogram.set_timestamp_ms(1_500_000_000_000);
+        assert_eq!(histogram.get_timestamp_ms(), 1_500_000_000_000);
+        assert_eq!(histogram.metric().get_timestamp_ms(), 1_500_000_000_000);
+
+        // Children of a vector are created on first access.
+        let vec = HistogramVec::new(HistogramOpts::new("test_vec", "test help"), &["l"]).unwrap();
+        let child = vec.with_label_values(&["a"]);
+        assert!(created(&child).0 > 0);
+    }
+
+    #[test]
     fn test_histogram_coarse_timer() {
         let opts = HistogramOpts::new("test1", "test help");
         let histogram = Histogram::with_opts(opts).unwrap();
 
+        let (clock, _guard) = clock::manual_for_test();
         let timer = histogram.start_coarse_timer();
-        thread::sleep(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
         timer.observe_duration();
 
         let timer = histogram.start_coarse_timer();
+        let c = clock.clone();
         let handler = thread::spawn(move || {
             let _timer = timer;
-            thread::sleep(Duration::from_millis(400));
+            c.advance(Duration::from_millis(400));
         });
         assert!(handler.join().is_ok());
 
-        histogram.observe_closure_duration(|| {
-            thread::sleep(Duration::from_millis(400));
+        histogram.observe_closure_duration_coarse(|| {
+            clock.advance(Duration::from_millis(400));
         });
 
         let mut mfs = histogram.collect();
@@ -1030,11 +1223,10 @@ mod tests {
         let m = mf.get_metric().get(0).unwrap();
         let proto_histogram = m.get_histogram();
         assert_eq!(proto_histogram.get_sample_count(), 3);
-        assert!((proto_histogram.get_sample_sum() - 0.0) > EPSILON);
+        assert!((proto_histogram.get_sample_sum() - 0.9).abs() < f64::EPSILON);
     }
 
     #[test]
-    #[cfg(feature = "nightly")]
     fn test_instant_on_smp() {
         let zero = Duration::from_millis(0);
         for i in 0..100_000 {
@@ -1101,6 +1293,66 @@ mod tests {
                 assert_eq!(got.unwrap(), vec);
             }
         }
+
+        let exponential_range_table = vec![
+            (1.0, 1000.0, 4, true, vec![1.0, 10.0, 100.0, 1000.0]),
+            (0.5, 8.0, 5, true, vec![0.5, 1.0, 2.0, 4.0, 8.0]),
+            (1.0, 10.0, 1, true, vec![1.0]),
+            (0.0, 10.0, 3, false, vec![]),
+            (10.0, 10.0, 3, false, vec![]),
+            (1.0, 10.0, 0, false, vec![]),
+        ];
+
+        for (param1, param2, param3, is_ok, vec) in exponential_range_table {
+            let got = exponential_buckets_range(param1, param2, param3);
+            assert_eq!(got.is_ok(), is_ok);
+            if let Ok(got) = got {
+                assert_eq!(got.len(), vec.len());
+                for (g, v) in got.iter().zip(vec.iter()) {
+                    assert!((g - v).abs() < 1e-9);
+                }
+            }
+        }
+
+        let log_linear_table = vec![
+            (0, 2, 1, true, vec![1.0, 10.0, 100.0]),
+            (-1, 0, 3, true, vec![0.1, 0.4, 0.7, 1.0]),
+            (
+                0,
+                1,
+                9,
+                true,
+                vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0],
+            ),
+            (-3, -3, 9, false, vec![]),
+            (-3, 0, 0, false, vec![]),
+        ];
+
+        for (param1, param2, param3, is_ok, vec) in log_linear_table {
+            let got = log_linear_buckets(param1, param2, param3);
+            assert_eq!(got.is_ok(), is_ok);
+            if let Ok(got) = got {
+                assert_eq!(got, vec);
+            }
+        }
+    }
+
+    #[test]
+    fn test_buckets_builder() {
+        let buckets = Buckets::new()
+            .merge(vec![5.0, 1.0])
+            .merge(linear_buckets(1.0, 1.0, 3).unwrap())
+            .merge(vec![f64::INFINITY])
+            .build()
+            .unwrap();
+        assert_eq!(buckets, vec![1.0, 2.0, 3.0, 5.0]);
+
+        let buckets = Buckets::from(vec![0.5]).merge(vec![0.25]).build().unwrap();
+        assert_eq!(buckets, vec![0.25, 0.5]);
+
+        assert!(Buckets::new().build().is_err());
+        assert!(Buckets::new().merge(vec![1.0, f64::NAN]).build().is_err());
+        assert!(Buckets::new().merge(vec![f64::INFINITY]).build().is_err());
     }
 
     #[test]
@@ -0,0 +1,202 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::desc::{Desc, Describer};
+use crate::errors::Result;
+use crate::metrics::{Collector, Metric, Opts};
+use crate::proto;
+use crate::value::make_label_pairs;
+use crate::vec::{MetricVec, MetricVecBuilder};
+
+const INFO_SUFFIX: &str = "_info";
+
+#[derive(Debug)]
+struct InfoCore {
+    desc: Desc,
+    label_pairs: Vec<proto::LabelPair>,
+}
+
+/// A [`Metric`] that exposes textual information about the target, such as
+/// build or version information, as labels of a sample that is always 1.
+///
+/// The fully-qualified name gets an `_info` suffix if it does not end with one
+/// already. It is exported as a gauge.
+///
+/// # Examples
+///
+/// ```
+/// use prometheus::Info;
+/// # use std::collections::HashMap;
+///
+/// let mut labels = HashMap::new();
+/// labels.insert("version".to_owned(), "1.0.0".to_owned());
+/// let info = Info::new("build", "Build information.", labels).unwrap();
+/// assert_eq!(info.fq_name(), "build_info");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Info {
+    core: Arc<InfoCore>,
+}
+
+impl Info {
+    /// Create an [`Info`] with the `name` and `help` arguments, exposing
+    /// `info` as its labels.
+    pub fn new<S1: Into<String>, S2: Into<String>>(
+        name: S1,
+        help: S2,
+        info: HashMap<String, String>,
+    ) -> Result<Info> {
+        let opts = Opts::new(name, help).const_labels(info);
+        Info::with_opts(opts)
+    }
+
+    /// Create an [`Info`] with the `opts` options. The const labels of `opts`
+    /// are the exposed information.
+    pub fn with_opts(opts: Opts) -> Result<Info> {
+        Info::with_opts_and_label_values(&info_opts(opts), &[])
+    }
+
+    fn with_opts_and_label_values(opts: &Opts, label_values: &[&str]) -> Result<Info> {
+        let desc = opts.describe()?;
+        let label_pairs = make_label_pairs(&desc, label_values)?;
+
+        Ok(Info {
+            core: Arc::new(InfoCore { desc, label_pairs }),
+        })
+    }
+
+    /// The fully qualified name for this info, always ending with `_info`.
+    pub fn fq_name(&self) -> &str {
+        &self.core.desc.fq_name
+    }
+}
+
+// `info_opts` appends the `_info` suffix to the name of `opts` unless the
+// fully-qualified name already has it.
+fn info_opts(mut opts: Opts) -> Opts {
+    if !opts.fq_name().ends_with(INFO_SUFFIX) {
+        opts.name.push_str(INFO_SUFFIX);
+    }
+    opts
+}
+
+impl Metric for Info {
+    fn metric(&self) -> proto::Metric {
+        let mut gauge = proto::Gauge::default();
+        gauge.set_value(1.0);
+
+        let mut m = proto::Metric::default();
+        m.set_label(from_vec!(self.core.label_pairs.clone()));
+        m.set_gauge(gauge);
+
+        m
+    }
+}
+
+impl Collector for Info {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![&self.core.desc]
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        let mut m = proto::MetricFamily::default();
+        m.set_name(self.core.desc.fq_name.clone());
+        m.set_help(self.core.desc.help.clone());
+        m.set_field_type(proto::MetricType::GAUGE);
+        m.set_metric(from_vec!(vec![self.metric()]));
+
+        vec![m]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InfoVecBuilder {}
+
+impl MetricVecBuilder for InfoVecBuilder {
+    type M = Info;
+    type P = Opts;
+
+    fn build(&self, opts: &Opts, vals: &[&str]) -> Result<Info> {
+        Info::with_opts_and_label_values(opts, vals)
+    }
+}
+
+/// A [`Collector`] that bundles a set of [`Info`]s that all share the same
+/// [`Desc`], but have different values for their variable labels. This is
+/// used if the information is only known at runtime, a child is exposed as
+/// soon as it is created with
+/// [`with_label_values`](MetricVec::with_label_values).
+///
+/// # Examples
+///
+/// ```
+/// use prometheus::{InfoVec, Opts};
+///
+/// let info_vec = InfoVec::new(Opts::new("build", "Build information."), &["version"]).unwrap();
+/// info_vec.with_label_values(&["1.0.0"]);
+/// ```
+pub type InfoVec = MetricVec<InfoVecBuilder>;
+
+impl InfoVec {
+    /// Create a new [`InfoVec`] based on the provided [`Opts`] and
+    /// partitioned by the given label names. At least one label name must be
+    /// provided.
+    pub fn new(opts: Opts, label_names: &[&str]) -> Result<InfoVec> {
+        let variable_names = label_names.iter().map(|s| (*s).to_owned()).collect();
+        let opts = info_opts(opts).variable_labels(variable_names);
+        let metric_vec = MetricVec::create(proto::MetricType::GAUGE, InfoVecBuilder {}, opts)?;
+
+        Ok(metric_vec as InfoVec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::{Encoder, TextEncoder};
+
+    #[test]
+    fn test_info() {
+        let mut labels = HashMap::new();
+        labels.insert("version".to_owned(), "1.0.0".to_owned());
+        labels.insert("revision".to_owned(), "abc".to_owned());
+        let info = Info::new("test_build", "test help", labels).unwrap();
+        assert_eq!(info.fq_name(), "test_build_info");
+
+        let opts = Opts::new("test_build_info", "test help").namespace("ns");
+        let info2 = Info::with_opts(opts).unwrap();
+        assert_eq!(info2.fq_name(), "ns_test_build_info");
+
+        let mfs = info.collect();
+        assert_eq!(mfs.len(), 1);
+        assert_eq!(mfs[0].get_field_type(), proto::MetricType::GAUGE);
+
+        let mut writer = Vec::<u8>::new();
+        TextEncoder::new().encode(&mfs, &mut writer).unwrap();
+        let ans = r##"# HELP test_build_info test help
+# TYPE test_build_info gauge
+test_build_info{revision="abc",version="1.0.0"} 1
+"##;
+        assert_eq!(ans, String::from_utf8(writer).unwrap());
+    }
+
+    #[test]
+    fn test_info_vec() {
+        let vec = InfoVec::new(Opts::new("test_build", "test help"), &["version"]).unwrap();
+        assert_eq!(vec.desc()[0].fq_name, "test_build_info");
+        assert!(vec.collect()[0].get_metric().is_empty());
+
+        vec.with_label_values(&["1.0.0"]);
+        vec.with_label_values(&["1.0.0"]);
+        let mfs = vec.collect();
+        assert_eq!(mfs[0].get_metric().len(), 1);
+        let m = &mfs[0].get_metric()[0];
+        assert_eq!(m.get_label()[0].get_value(), "1.0.0");
+        assert!((m.get_gauge().get_value() - 1.0).abs() < f64::EPSILON);
+
+        assert!(vec.remove_label_values(&["1.0.0"]).is_ok());
+        assert!(vec.collect()[0].get_metric().is_empty());
+    }
+}
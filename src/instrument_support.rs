@@ -0,0 +1,53 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Helpers for the code generated by [`instrument`](crate::instrument).
+
+use crate::atomic64::Atomic;
+use crate::counter::{GenericCounter, GenericCounterVec};
+use crate::histogram::{Histogram, HistogramVec};
+
+/// A histogram that observes the durations of an instrumented function.
+pub trait InstrumentHistogram {
+    /// Return the histogram for `function`.
+    fn instrumented_histogram(&self, function: &str) -> Histogram;
+}
+
+impl InstrumentHistogram for Histogram {
+    fn instrumented_histogram(&self, _: &str) -> Histogram {
+        self.clone()
+    }
+}
+
+impl InstrumentHistogram for HistogramVec {
+    /// Panics if the vector does not have exactly one label.
+    fn instrumented_histogram(&self, function: &str) -> Histogram {
+        self.with_label_values(&[function])
+    }
+}
+
+/// A counter that counts the calls, or failed calls, of an instrumented
+/// function.
+pub trait InstrumentCounter {
+    /// Increase the counter for `function` by 1.
+    fn inc_instrumented(&self, function: &str);
+}
+
+impl<P: Atomic> InstrumentCounter for GenericCounter<P> {
+    fn inc_instrumented(&self, _: &str) {
+        self.inc();
+    }
+}
+
+impl<P: Atomic> InstrumentCounter for GenericCounterVec<P> {
+    /// Panics if the vector does not have exactly one label.
+    fn inc_instrumented(&self, function: &str) {
+        self.with_label_values(&[function]).inc();
+    }
+}
+
+/// Call `f`. Passing the body of an instrumented function through this makes
+/// it an `FnOnce` closure, which may return borrows of captured `&mut`
+/// arguments.
+pub fn call_once<R, F: FnOnce() -> R>(f: F) -> R {
+    f()
+}
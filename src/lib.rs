@@ -102,13 +102,20 @@ additional functionality.
 
 # Features
 
-This library supports four features:
+This library supports ten features:
 
 * `gen`: To generate protobuf client with the latest protobuf version instead of
   using the pre-generated client.
 * `nightly`: Enable nightly only features.
 * `process`: For collecting process info.
 * `push`: Enable push support.
+* `push-async`: Enable asynchronous push support, on top of `push`.
+* `remote-write`: Enable the Prometheus remote-write exporter.
+* `otlp`: Enable the OpenTelemetry OTLP exporter.
+* `statsd`: Enable the StatsD and DogStatsD exporter.
+* `serde`: Implement `Serialize` and `Deserialize` for the metric model, and
+  enable the `JsonEncoder`.
+* `macros`: Enable the [`instrument`](attr.instrument.html) attribute macro.
 
 */
 
@@ -154,26 +161,59 @@ extern crate lazy_static;
 mod macros;
 mod atomic64;
 mod auto_flush;
+#[cfg(any(feature = "push", feature = "remote-write"))]
+mod backoff;
+mod const_metric;
 mod counter;
 mod desc;
 mod encoder;
 mod errors;
 mod gauge;
+mod gauge_histogram;
 mod histogram;
+mod info;
+mod local_flush;
 mod metrics;
 #[cfg(feature = "push")]
 mod push;
+#[cfg(feature = "push")]
+mod push_client;
+#[cfg(feature = "push")]
+mod pusher;
 mod registry;
+mod state_set;
+#[cfg(all(
+    test,
+    any(feature = "push", feature = "remote-write", feature = "otlp")
+))]
+mod test_http;
 mod value;
 mod vec;
+#[cfg(any(feature = "remote-write", feature = "otlp"))]
+mod wire;
 
 // Public for generated code.
 #[doc(hidden)]
 pub mod timer;
 
+// Public for generated code.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod instrument_support;
+
 #[cfg(all(feature = "process", target_os = "linux"))]
 pub mod process_collector;
 
+#[cfg(feature = "otlp")]
+pub mod otlp;
+#[cfg(feature = "remote-write")]
+pub mod remote_write;
+#[cfg(feature = "statsd")]
+pub mod statsd;
+
+pub mod clock;
+pub mod future;
+
 pub mod local {
     /*!
 
@@ -183,11 +223,16 @@ pub mod local {
     pub use super::counter::{
         CounterWithValueType, LocalCounter, LocalCounterVec, LocalIntCounter, LocalIntCounterVec,
     };
+    pub use super::gauge::{
+        GaugeWithValueType, LocalGauge, LocalGaugeVec, LocalIntGauge, LocalIntGaugeVec,
+    };
     pub use super::histogram::{LocalHistogram, LocalHistogramTimer, LocalHistogramVec};
+    pub use super::local_flush::{flush_local_metrics, spawn_local_flusher, SharedLocalMetric};
     pub use super::metrics::{LocalMetric, MayFlush};
 
     pub use super::auto_flush::{
-        AFLocalCounter, AFLocalHistogram, CounterDelegator, HistogramDelegator,
+        AFLocalCounter, AFLocalGauge, AFLocalHistogram, CounterDelegator, GaugeDelegator,
+        HistogramDelegator,
     };
 }
 
@@ -203,11 +248,14 @@ pub mod core {
         GenericCounter, GenericCounterVec, GenericLocalCounter, GenericLocalCounterVec,
     };
     pub use super::desc::{Desc, Describer};
-    pub use super::gauge::{GenericGauge, GenericGaugeVec};
+    pub use super::gauge::{
+        GenericGauge, GenericGaugeVec, GenericLocalGauge, GenericLocalGaugeVec,
+    };
     pub use super::metrics::{Collector, Metric, Opts};
     pub use super::vec::{MetricVec, MetricVecBuilder};
 }
 
+pub use self::const_metric::ConstMetric;
 pub use self::counter::{
     Counter, CounterVec, DeleteOnDropCounter, IntCounter, IntCounterVec, UIntCounter,
     UIntCounterVec,
@@ -216,20 +264,40 @@ pub use self::encoder::Encoder;
 #[cfg(feature = "protobuf")]
 pub use self::encoder::ProtobufEncoder;
 pub use self::encoder::TextEncoder;
+pub use self::encoder::{GraphiteEncoder, GraphiteLabels, InfluxLineEncoder};
+#[cfg(feature = "serde")]
+pub use self::encoder::{JsonEncoder, JSON_FORMAT};
+pub use self::encoder::{OpenMetricsEncoder, OPENMETRICS_FORMAT};
 #[cfg(feature = "protobuf")]
 pub use self::encoder::{PROTOBUF_FORMAT, TEXT_FORMAT};
 pub use self::errors::{Error, Result};
 pub use self::gauge::{
     DeleteOnDropGauge, Gauge, GaugeVec, IntGauge, IntGaugeVec, UIntGauge, UIntGaugeVec,
 };
+pub use self::gauge_histogram::{GaugeHistogram, GaugeHistogramVec};
 pub use self::histogram::DEFAULT_BUCKETS;
-pub use self::histogram::{exponential_buckets, linear_buckets};
+pub use self::histogram::{
+    exponential_buckets, exponential_buckets_range, linear_buckets, log_linear_buckets, Buckets,
+};
 pub use self::histogram::{Histogram, HistogramOpts, HistogramTimer, HistogramVec};
+pub use self::info::{Info, InfoVec};
 pub use self::metrics::Opts;
 #[cfg(feature = "push")]
 pub use self::push::{
-    hostname_grouping_key, push_add_collector, push_add_metrics, push_collector, push_metrics,
-    BasicAuthentication,
+    delete_metrics, hostname_grouping_key, push_add_collector, push_add_metrics, push_collector,
+    push_metrics, BasicAuthentication, PushFormat,
 };
+#[cfg(feature = "push-async")]
+pub use self::push::{
+    delete_metrics_async, push_add_collector_async, push_add_metrics_async, push_collector_async,
+    push_metrics_async,
+};
+#[cfg(feature = "push")]
+pub use self::push_client::{PushClient, PushOptions};
+#[cfg(feature = "push")]
+pub use self::pusher::{Pusher, PusherHandle};
 pub use self::registry::Registry;
 pub use self::registry::{default_registry, gather, register, unregister};
+pub use self::state_set::{StateSet, StateSetVec};
+#[cfg(feature = "macros")]
+pub use static_metric_proc_macros::instrument;
@@ -0,0 +1,201 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::metrics::LocalMetric;
+
+type SharedMetric = Mutex<dyn LocalMetric + Send>;
+
+lazy_static! {
+    static ref LOCAL_METRICS: Mutex<Vec<Weak<SharedMetric>>> = Mutex::new(Vec::new());
+}
+
+// Taken for writing by the tests that check when metrics are flushed, so
+// that `Registry::gather` in other tests does not flush them meanwhile.
+#[cfg(test)]
+lazy_static! {
+    pub(crate) static ref GATHER_FLUSH: parking_lot::RwLock<()> = parking_lot::RwLock::new(());
+}
+
+/// A local metric that can also be flushed from other threads.
+///
+/// Local metrics buffer updates until they are flushed by the thread owning
+/// them, so updates made on a thread that went idle are invisible to scrapes.
+/// Wrapping a local metric in a [`SharedLocalMetric`] registers it in a global
+/// list of live local metrics, which is flushed by every
+/// [`Registry::gather`](crate::Registry::gather), by [`flush_local_metrics`]
+/// and by the thread started by [`spawn_local_flusher`]. It is flushed one
+/// last time when dropped, e.g. when the thread owning it exits.
+///
+/// Accessing the metric takes an uncontended lock, which is still much
+/// cheaper than updating the shared metric directly.
+///
+/// # Examples
+///
+/// ```
+/// use prometheus::local::SharedLocalMetric;
+/// use prometheus::IntCounter;
+///
+/// let counter = IntCounter::new("requests", "Number of requests.").unwrap();
+/// let local = SharedLocalMetric::new(counter.local());
+///
+/// local.with(|c| c.inc());
+/// assert_eq!(counter.get(), 0);
+///
+/// prometheus::local::flush_local_metrics();
+/// assert_eq!(counter.get(), 1);
+/// ```
+pub struct SharedLocalMetric<M: LocalMetric + Send + 'static> {
+    inner: Arc<Mutex<M>>,
+}
+
+impl<M: LocalMetric + Send + 'static> SharedLocalMetric<M> {
+    /// Wrap `metric` and register it for flushing.
+    pub fn new(metric: M) -> SharedLocalMetric<M> {
+        let inner = Arc::new(Mutex::new(metric));
+        let shared: Arc<SharedMetric> = inner.clone();
+
+        let mut metrics = LOCAL_METRICS.lock();
+        metrics.retain(|m| m.strong_count() > 0);
+        metrics.push(Arc::downgrade(&shared));
+
+        SharedLocalMetric { inner }
+    }
+
+    /// Call `f` with the wrapped local metric.
+    ///
+    /// Flushing from other threads is skipped while `f` runs.
+    #[inline]
+    pub fn with<R, F: FnOnce(&mut M) -> R>(&self, f: F) -> R {
+        f(&mut self.inner.lock())
+    }
+
+    /// Flush the wrapped local metric.
+    pub fn flush(&self) {
+        self.inner.lock().flush();
+    }
+}
+
+impl<M: LocalMetric + Send + 'static> LocalMetric for SharedLocalMetric<M> {
+    fn flush(&self) {
+        SharedLocalMetric::flush(self);
+    }
+}
+
+impl<M: LocalMetric + Send + 'static> Drop for SharedLocalMetric<M> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<M: LocalMetric + Send + 'static> fmt::Debug for SharedLocalMetric<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedLocalMetric")
+    }
+}
+
+/// Flush all live [`SharedLocalMetric`]s.
+///
+/// Metrics that are being accessed by their owning thread at the same time
+/// are skipped, they will be picked up by the next flush.
+pub fn flush_local_metrics() {
+    // Collect the metrics first, so that flushing does not block
+    // registering new ones.
+    let live: Vec<_> = {
+        let mut metrics = LOCAL_METRICS.lock();
+        metrics.retain(|m| m.strong_count() > 0);
+        metrics.iter().filter_map(Weak::upgrade).collect()
+    };
+
+    for m in live {
+        if let Some(m) = m.try_lock() {
+            m.flush();
+        }
+    }
+}
+
+/// Spawn a background thread that calls [`flush_local_metrics`] every
+/// `interval`, for processes whose metrics are not gathered through a
+/// [`Registry`](crate::Registry).
+pub fn spawn_local_flusher(interval: Duration) -> io::Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name("local metrics flusher".to_owned())
+        .spawn(move || loop {
+            thread::sleep(interval);
+            flush_local_metrics();
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::IntCounter;
+    use crate::histogram::{Histogram, HistogramOpts};
+    use crate::registry::Registry;
+
+    #[test]
+    fn test_shared_local_metric() {
+        let _isolated = GATHER_FLUSH.write();
+        let counter = IntCounter::new("test_shared_local_counter", "test help").unwrap();
+        let histogram = Histogram::with_opts(HistogramOpts::new(
+            "test_shared_local_histogram",
+            "test help",
+        ))
+        .unwrap();
+
+        let local_counter = SharedLocalMetric::new(counter.local());
+        let local_histogram = SharedLocalMetric::new(histogram.local());
+        local_counter.with(|c| c.inc_by(3));
+        local_histogram.with(|h| h.observe(1.0));
+        assert_eq!(counter.get(), 0);
+        assert_eq!(histogram.get_sample_count(), 0);
+
+        flush_local_metrics();
+        assert_eq!(counter.get(), 3);
+        assert_eq!(histogram.get_sample_count(), 1);
+
+        // Metrics in use are skipped.
+        local_counter.with(|c| {
+            c.inc();
+            flush_local_metrics();
+        });
+        assert_eq!(counter.get(), 3);
+
+        drop(local_counter);
+        assert_eq!(counter.get(), 4);
+    }
+
+    #[test]
+    fn test_shared_local_metric_gather() {
+        let counter = IntCounter::new("test_shared_local_gather", "test help").unwrap();
+        let r = Registry::new();
+        r.register(Box::new(counter.clone())).unwrap();
+
+        let c = counter.clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let t = thread::spawn(move || {
+            let local = SharedLocalMetric::new(c.local());
+            local.with(|c| c.inc_by(2));
+            tx.send(()).unwrap();
+            // Stay idle until the scrape is done.
+            done_rx.recv().unwrap();
+            local.with(|c| c.inc());
+        });
+
+        rx.recv().unwrap();
+        let mfs = r.gather();
+        assert_eq!(mfs[0].get_metric()[0].get_counter().get_value() as u64, 2);
+
+        // Thread exit flushes the rest.
+        done_tx.send(()).unwrap();
+        t.join().unwrap();
+        assert_eq!(counter.get(), 3);
+    }
+}
@@ -451,3 +451,187 @@ macro_rules! register_histogram_vec {
         register_histogram_vec!(histogram_opts!($NAME, $HELP, $BUCKETS), $LABELS_NAMES)
     }};
 }
+
+/// Create a [`GaugeHistogram`] and registers to default registry.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate prometheus;
+/// # fn main() {
+/// let opts = histogram_opts!("test_macro_gauge_histogram", "help");
+/// let res1 = register_gauge_histogram!(opts);
+/// assert!(res1.is_ok());
+///
+/// let res2 = register_gauge_histogram!("test_macro_gauge_histogram_2", "help");
+/// assert!(res2.is_ok());
+///
+/// let res3 = register_gauge_histogram!("test_macro_gauge_histogram_3",
+///                                       "help",
+///                                       vec![1.0, 2.0]);
+/// assert!(res3.is_ok());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! register_gauge_histogram {
+    ($NAME:expr, $HELP:expr) => {
+        register_gauge_histogram!(histogram_opts!($NAME, $HELP))
+    };
+
+    ($NAME:expr, $HELP:expr, $BUCKETS:expr) => {
+        register_gauge_histogram!(histogram_opts!($NAME, $HELP, $BUCKETS))
+    };
+
+    ($HOPTS:expr) => {{
+        let gauge_histogram = $crate::GaugeHistogram::with_opts($HOPTS).unwrap();
+        $crate::register(Box::new(gauge_histogram.clone())).map(|_| gauge_histogram)
+    }};
+}
+
+/// Create a [`GaugeHistogramVec`] and registers to default registry.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate prometheus;
+/// # fn main() {
+/// let opts = histogram_opts!("test_macro_gauge_histogram_vec_1", "help");
+/// let gauge_histogram_vec = register_gauge_histogram_vec!(opts, &["a", "b"]);
+/// assert!(gauge_histogram_vec.is_ok());
+///
+/// let gauge_histogram_vec =
+///     register_gauge_histogram_vec!("test_macro_gauge_histogram_vec_2", "help", &["a", "b"]);
+/// assert!(gauge_histogram_vec.is_ok());
+///
+/// let gauge_histogram_vec = register_gauge_histogram_vec!("test_macro_gauge_histogram_vec_3",
+///                                                         "help",
+///                                                         &["test_label"],
+///                                                         vec![0.0, 1.0, 2.0]);
+/// assert!(gauge_histogram_vec.is_ok());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! register_gauge_histogram_vec {
+    ($HOPTS:expr, $LABELS_NAMES:expr) => {{
+        let gauge_histogram_vec = $crate::GaugeHistogramVec::new($HOPTS, $LABELS_NAMES).unwrap();
+        $crate::register(Box::new(gauge_histogram_vec.clone())).map(|_| gauge_histogram_vec)
+    }};
+
+    ($NAME:expr, $HELP:expr, $LABELS_NAMES:expr) => {{
+        register_gauge_histogram_vec!(histogram_opts!($NAME, $HELP), $LABELS_NAMES)
+    }};
+
+    ($NAME:expr, $HELP:expr, $LABELS_NAMES:expr, $BUCKETS:expr) => {{
+        register_gauge_histogram_vec!(histogram_opts!($NAME, $HELP, $BUCKETS), $LABELS_NAMES)
+    }};
+}
+
+/// Create an [`Info`] and registers to default registry.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate prometheus;
+/// # fn main() {
+/// let opts = opts!("test_macro_info_1", "help", labels!{"version" => "1.0.0",});
+/// let res1 = register_info!(opts);
+/// assert!(res1.is_ok());
+///
+/// let res2 = register_info!("test_macro_info_2", "help", labels!{"version" => "1.0.0",});
+/// assert!(res2.is_ok());
+/// assert_eq!(res2.unwrap().fq_name(), "test_macro_info_2_info");
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! register_info {
+    ($OPTS:expr) => {{
+        let info = $crate::Info::with_opts($OPTS).unwrap();
+        $crate::register(Box::new(info.clone())).map(|_| info)
+    }};
+
+    ($NAME:expr, $HELP:expr, $INFO:expr) => {{
+        register_info!(opts!($NAME, $HELP, $INFO))
+    }};
+}
+
+/// Create an [`InfoVec`] and registers to default registry.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate prometheus;
+/// # fn main() {
+/// let opts = opts!("test_macro_info_vec_1", "help");
+/// let info_vec = register_info_vec!(opts, &["version"]);
+/// assert!(info_vec.is_ok());
+///
+/// let info_vec = register_info_vec!("test_macro_info_vec_2", "help", &["version"]);
+/// assert!(info_vec.is_ok());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! register_info_vec {
+    ($OPTS:expr, $LABELS_NAMES:expr) => {{
+        let info_vec = $crate::InfoVec::new($OPTS, $LABELS_NAMES).unwrap();
+        $crate::register(Box::new(info_vec.clone())).map(|_| info_vec)
+    }};
+
+    ($NAME:expr, $HELP:expr, $LABELS_NAMES:expr) => {{
+        register_info_vec!(opts!($NAME, $HELP), $LABELS_NAMES)
+    }};
+}
+
+/// Create a [`StateSet`] and registers to default registry.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate prometheus;
+/// # fn main() {
+/// let opts = opts!("test_macro_state_set_1", "help");
+/// let res1 = register_state_set!(opts, &["a", "b"]);
+/// assert!(res1.is_ok());
+///
+/// let res2 = register_state_set!("test_macro_state_set_2", "help", &["a", "b"]);
+/// assert!(res2.is_ok());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! register_state_set {
+    ($OPTS:expr, $STATES:expr) => {{
+        let state_set = $crate::StateSet::with_opts($OPTS, $STATES).unwrap();
+        $crate::register(Box::new(state_set.clone())).map(|_| state_set)
+    }};
+
+    ($NAME:expr, $HELP:expr, $STATES:expr) => {{
+        register_state_set!(opts!($NAME, $HELP), $STATES)
+    }};
+}
+
+/// Create a [`StateSetVec`] and registers to default registry.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate prometheus;
+/// # fn main() {
+/// let opts = opts!("test_macro_state_set_vec_1", "help");
+/// let state_set_vec = register_state_set_vec!(opts, &["pool"], &["a", "b"]);
+/// assert!(state_set_vec.is_ok());
+///
+/// let state_set_vec =
+///     register_state_set_vec!("test_macro_state_set_vec_2", "help", &["pool"], &["a", "b"]);
+/// assert!(state_set_vec.is_ok());
+/// # }
+/// ```
+#[macro_export(local_inner_macros)]
+macro_rules! register_state_set_vec {
+    ($OPTS:expr, $LABELS_NAMES:expr, $STATES:expr) => {{
+        let state_set_vec = $crate::StateSetVec::new($OPTS, $LABELS_NAMES, $STATES).unwrap();
+        $crate::register(Box::new(state_set_vec.clone())).map(|_| state_set_vec)
+    }};
+
+    ($NAME:expr, $HELP:expr, $LABELS_NAMES:expr, $STATES:expr) => {{
+        register_state_set_vec!(opts!($NAME, $HELP), $LABELS_NAMES, $STATES)
+    }};
+}
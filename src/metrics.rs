@@ -3,6 +3,8 @@
 
 use std::cmp::{Eq, Ord, Ordering, PartialOrd};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::desc::{Desc, Describer};
 use crate::errors::Result;
@@ -189,6 +191,45 @@ impl PartialOrd for LabelPair {
     }
 }
 
+/// The creation time of a cumulative metric, i.e. the start of the period its
+/// values accumulate over. Exported so that scrapers can tell a counter that
+/// was reset from a series that was just created.
+#[derive(Debug)]
+pub(crate) struct Created {
+    // Nanoseconds since the UNIX epoch.
+    nanos: AtomicU64,
+}
+
+impl Created {
+    pub fn now() -> Created {
+        Created {
+            nanos: AtomicU64::new(now_nanos()),
+        }
+    }
+
+    /// Move the creation time to now, when the metric is reset.
+    pub fn reset(&self) {
+        self.nanos.store(now_nanos(), AtomicOrdering::Relaxed);
+    }
+
+    /// Return the creation time as seconds and nanoseconds since the UNIX
+    /// epoch, as stored in a protobuf `Timestamp`.
+    pub fn to_parts(&self) -> (i64, i32) {
+        let nanos = self.nanos.load(AtomicOrdering::Relaxed);
+        (
+            (nanos / 1_000_000_000) as i64,
+            (nanos % 1_000_000_000) as i32,
+        )
+    }
+}
+
+fn now_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
 /// `build_fq_name` joins the given three name components by "_". Empty name
 /// components are ignored. If the name parameter itself is empty, an empty
 /// string is returned, no matter what. [`Metric`] implementations included in this
@@ -0,0 +1,902 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+/*!
+
+Export metrics to OpenTelemetry with OTLP.
+
+[`ExportMetricsRequest::from_metric_families`] converts gathered metric
+families into the OTLP metrics model, and an [`OtlpExporter`] periodically
+sends them to an OTLP/HTTP receiver, e.g. an OpenTelemetry collector, encoded
+with protobuf.
+
+Metrics keep their names, and labels become attributes. All data points have
+cumulative temporality: counters become monotonic sums, gauges and untyped
+metrics become gauges, histograms become histograms with explicit bounds, and
+summaries become summaries. The start time of a data point is its created
+timestamp if it has one, else the time the conversion was told the process
+started.
+
+OTLP histograms only count observations, so a gauge histogram becomes gauges
+named like its OpenMetrics samples instead: `<name>_bucket`, with an `le`
+attribute and cumulative values, `<name>_gcount` and `<name>_gsum`.
+
+*/
+
+use std::fmt;
+use std::io;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+
+use crate::counter::IntCounter;
+use crate::errors::{Error, Result};
+use crate::gauge::Gauge;
+use crate::histogram::BUCKET_LABEL;
+use crate::proto::{self, MetricType};
+use crate::registry::{default_registry, Registry};
+use crate::wire;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// AggregationTemporality.AGGREGATION_TEMPORALITY_CUMULATIVE.
+const CUMULATIVE: u64 = 2;
+
+const POSITIVE_INF: &str = "+Inf";
+
+/// A key with a string value, used for attributes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyValue {
+    /// The key.
+    pub key: String,
+    /// The value.
+    pub value: String,
+}
+
+impl KeyValue {
+    /// Create a [`KeyValue`].
+    pub fn new<S1: Into<String>, S2: Into<String>>(key: S1, value: S2) -> KeyValue {
+        KeyValue {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// A data point of a sum or a gauge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberDataPoint {
+    /// The labels of the metric.
+    pub attributes: Vec<KeyValue>,
+    /// The start of the cumulative period, in nanoseconds since the UNIX epoch.
+    pub start_time_unix_nano: u64,
+    /// The time of the value, in nanoseconds since the UNIX epoch.
+    pub time_unix_nano: u64,
+    /// The value.
+    pub value: f64,
+}
+
+/// A data point of a histogram.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramDataPoint {
+    /// The labels of the metric.
+    pub attributes: Vec<KeyValue>,
+    /// The start of the cumulative period, in nanoseconds since the UNIX epoch.
+    pub start_time_unix_nano: u64,
+    /// The time of the value, in nanoseconds since the UNIX epoch.
+    pub time_unix_nano: u64,
+    /// The number of observations.
+    pub count: u64,
+    /// The sum of the observations.
+    pub sum: f64,
+    /// The number of observations in each bucket, not cumulative, with one
+    /// more element than `explicit_bounds` for the `+Inf` bucket.
+    pub bucket_counts: Vec<u64>,
+    /// The finite upper bounds of the buckets.
+    pub explicit_bounds: Vec<f64>,
+}
+
+/// A quantile of a summary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValueAtQuantile {
+    /// The quantile, between 0 and 1.
+    pub quantile: f64,
+    /// The value at the quantile.
+    pub value: f64,
+}
+
+/// A data point of a summary.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SummaryDataPoint {
+    /// The labels of the metric.
+    pub attributes: Vec<KeyValue>,
+    /// The start of the cumulative period, in nanoseconds since the UNIX epoch.
+    pub start_time_unix_nano: u64,
+    /// The time of the value, in nanoseconds since the UNIX epoch.
+    pub time_unix_nano: u64,
+    /// The number of observations.
+    pub count: u64,
+    /// The sum of the observations.
+    pub sum: f64,
+    /// The quantiles.
+    pub quantile_values: Vec<ValueAtQuantile>,
+}
+
+/// The data points of a [`Metric`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Data {
+    /// The data points of a gauge.
+    Gauge(Vec<NumberDataPoint>),
+    /// The data points of a cumulative sum.
+    Sum {
+        /// The data points.
+        data_points: Vec<NumberDataPoint>,
+        /// Whether the sum only increases, true for counters.
+        is_monotonic: bool,
+    },
+    /// The data points of a cumulative histogram.
+    Histogram(Vec<HistogramDataPoint>),
+    /// The data points of a summary.
+    Summary(Vec<SummaryDataPoint>),
+}
+
+/// An OTLP metric, converted from a metric family.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metric {
+    /// The name of the metric family, with the suffix of the sample for
+    /// gauge histograms.
+    pub name: String,
+    /// The help of the metric family.
+    pub description: String,
+    /// The data points, one per metric of the family.
+    pub data: Data,
+}
+
+/// The message sent to an OTLP receiver, an `ExportMetricsServiceRequest`
+/// with a single resource and instrumentation scope.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportMetricsRequest {
+    /// The attributes of the resource, e.g. `service.name`.
+    pub resource: Vec<KeyValue>,
+    /// The metrics.
+    pub metrics: Vec<Metric>,
+}
+
+impl ExportMetricsRequest {
+    /// Convert gathered metric families into OTLP metrics. Data points
+    /// without an explicit timestamp get `time_unix_nano`, and those without
+    /// a created timestamp start at `start_time_unix_nano`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use prometheus::otlp::{Data, ExportMetricsRequest};
+    /// use prometheus::{IntCounter, Registry};
+    ///
+    /// let registry = Registry::new();
+    /// let counter = IntCounter::new("requests_total", "Number of requests.").unwrap();
+    /// registry.register(Box::new(counter.clone())).unwrap();
+    /// counter.inc();
+    ///
+    /// let request = ExportMetricsRequest::from_metric_families(&registry.gather(), 1, 2);
+    /// let metric = &request.metrics[0];
+    /// assert_eq!(metric.name, "requests_total");
+    /// match metric.data {
+    ///     Data::Sum { ref data_points, is_monotonic } => {
+    ///         assert!(is_monotonic);
+    ///         assert_eq!(data_points[0].value, 1.0);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn from_metric_families(
+        mfs: &[proto::MetricFamily],
+        start_time_unix_nano: u64,
+        time_unix_nano: u64,
+    ) -> ExportMetricsRequest {
+        let metrics = mfs
+            .iter()
+            .flat_map(|mf| convert(mf, start_time_unix_nano, time_unix_nano))
+            .collect();
+        ExportMetricsRequest {
+            resource: Vec::new(),
+            metrics,
+        }
+    }
+
+    /// Encode the request as a protobuf `ExportMetricsServiceRequest`
+    /// message.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        // ResourceMetrics.
+        wire::write_message(&mut buf, 1, |rm| {
+            wire::write_message(rm, 1, |resource| {
+                for kv in &self.resource {
+                    write_key_value(resource, 1, kv);
+                }
+            });
+            // ScopeMetrics.
+            wire::write_message(rm, 2, |sm| {
+                wire::write_message(sm, 1, |scope| {
+                    wire::write_bytes(scope, 1, env!("CARGO_PKG_NAME").as_bytes());
+                    wire::write_bytes(scope, 2, env!("CARGO_PKG_VERSION").as_bytes());
+                });
+                for metric in &self.metrics {
+                    wire::write_message(sm, 2, |m| encode_metric(m, metric));
+                }
+            });
+        });
+        buf
+    }
+}
+
+// Converts a metric family into a metric, or three for a gauge histogram.
+fn convert(mf: &proto::MetricFamily, default_start: u64, default_time: u64) -> Vec<Metric> {
+    let mut numbers = Vec::new();
+    let mut histograms = Vec::new();
+    let mut summaries = Vec::new();
+    let (mut buckets, mut gcounts, mut gsums) = (Vec::new(), Vec::new(), Vec::new());
+
+    for m in mf.get_metric() {
+        let attributes: Vec<_> = m
+            .get_label()
+            .iter()
+            .map(|lp| KeyValue::new(lp.get_name(), lp.get_value()))
+            .collect();
+        let time_unix_nano = if m.get_timestamp_ms() > 0 {
+            m.get_timestamp_ms() as u64 * 1_000_000
+        } else {
+            default_time
+        };
+        let created = match mf.get_field_type() {
+            MetricType::COUNTER if m.get_counter().has_created_timestamp() => {
+                Some(m.get_counter().get_created_timestamp())
+            }
+            MetricType::SUMMARY if m.get_summary().has_created_timestamp() => {
+                Some(m.get_summary().get_created_timestamp())
+            }
+            MetricType::HISTOGRAM if m.get_histogram().has_created_timestamp() => {
+                Some(m.get_histogram().get_created_timestamp())
+            }
+            _ => None,
+        };
+        let start_time_unix_nano = created
+            .map(|ts| ts.get_seconds() as u64 * 1_000_000_000 + ts.get_nanos() as u64)
+            .unwrap_or(default_start);
+
+        match mf.get_field_type() {
+            MetricType::COUNTER | MetricType::GAUGE | MetricType::UNTYPED => {
+                let value = match mf.get_field_type() {
+                    MetricType::COUNTER => m.get_counter().get_value(),
+                    MetricType::GAUGE => m.get_gauge().get_value(),
+                    #[allow(deprecated)]
+                    _ => m.get_untyped().get_value(),
+                };
+                numbers.push(NumberDataPoint {
+                    attributes,
+                    start_time_unix_nano,
+                    time_unix_nano,
+                    value,
+                });
+            }
+            MetricType::HISTOGRAM => {
+                let h = m.get_histogram();
+                let mut bucket_counts = Vec::with_capacity(h.get_bucket().len() + 1);
+                let mut explicit_bounds = Vec::with_capacity(h.get_bucket().len());
+                let mut previous = 0;
+                for b in h.get_bucket() {
+                    if b.get_upper_bound() == f64::INFINITY {
+                        break;
+                    }
+                    explicit_bounds.push(b.get_upper_bound());
+                    bucket_counts.push(b.get_cumulative_count().saturating_sub(previous));
+                    previous = b.get_cumulative_count();
+                }
+                bucket_counts.push(h.get_sample_count().saturating_sub(previous));
+                histograms.push(HistogramDataPoint {
+                    attributes,
+                    start_time_unix_nano,
+                    time_unix_nano,
+                    count: h.get_sample_count(),
+                    sum: h.get_sample_sum(),
+                    bucket_counts,
+                    explicit_bounds,
+                });
+            }
+            MetricType::GAUGE_HISTOGRAM => {
+                let h = m.get_histogram();
+                let point = |attributes, value| NumberDataPoint {
+                    attributes,
+                    start_time_unix_nano,
+                    time_unix_nano,
+                    value,
+                };
+                let mut inf_seen = false;
+                for b in h.get_bucket() {
+                    let le = if b.get_upper_bound() == f64::INFINITY {
+                        inf_seen = true;
+                        POSITIVE_INF.to_owned()
+                    } else {
+                        b.get_upper_bound().to_string()
+                    };
+                    let mut bucket_attributes = attributes.clone();
+                    bucket_attributes.push(KeyValue::new(BUCKET_LABEL, le));
+                    buckets.push(point(bucket_attributes, b.get_cumulative_count() as f64));
+                }
+                if !inf_seen {
+                    let mut bucket_attributes = attributes.clone();
+                    bucket_attributes.push(KeyValue::new(BUCKET_LABEL, POSITIVE_INF));
+                    buckets.push(point(bucket_attributes, h.get_sample_count() as f64));
+                }
+                gcounts.push(point(attributes.clone(), h.get_sample_count() as f64));
+                gsums.push(point(attributes, h.get_sample_sum()));
+            }
+            MetricType::SUMMARY => {
+                let s = m.get_summary();
+                summaries.push(SummaryDataPoint {
+                    attributes,
+                    start_time_unix_nano,
+                    time_unix_nano,
+                    count: s.get_sample_count(),
+                    sum: s.get_sample_sum(),
+                    quantile_values: s
+                        .get_quantile()
+                        .iter()
+                        .map(|q| ValueAtQuantile {
+                            quantile: q.get_quantile(),
+                            value: q.get_value(),
+                        })
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    let data = match mf.get_field_type() {
+        MetricType::COUNTER => Data::Sum {
+            data_points: numbers,
+            is_monotonic: true,
+        },
+        MetricType::GAUGE | MetricType::UNTYPED => Data::Gauge(numbers),
+        MetricType::HISTOGRAM => Data::Histogram(histograms),
+        MetricType::SUMMARY => Data::Summary(summaries),
+        MetricType::GAUGE_HISTOGRAM => {
+            let gauge = |suffix, points| Metric {
+                name: format!("{}{}", mf.get_name(), suffix),
+                description: mf.get_help().to_owned(),
+                data: Data::Gauge(points),
+            };
+            return vec![
+                gauge("_bucket", buckets),
+                gauge("_gcount", gcounts),
+                gauge("_gsum", gsums),
+            ];
+        }
+    };
+    vec![Metric {
+        name: mf.get_name().to_owned(),
+        description: mf.get_help().to_owned(),
+        data,
+    }]
+}
+
+fn write_key_value(buf: &mut Vec<u8>, field: u32, kv: &KeyValue) {
+    wire::write_message(buf, field, |m| {
+        wire::write_bytes(m, 1, kv.key.as_bytes());
+        // AnyValue.string_value.
+        wire::write_message(m, 2, |v| wire::write_bytes(v, 1, kv.value.as_bytes()));
+    });
+}
+
+fn encode_number_points(buf: &mut Vec<u8>, points: &[NumberDataPoint]) {
+    for p in points {
+        wire::write_message(buf, 1, |m| {
+            wire::write_fixed64(m, 2, p.start_time_unix_nano);
+            wire::write_fixed64(m, 3, p.time_unix_nano);
+            wire::write_double(m, 4, p.value);
+            for kv in &p.attributes {
+                write_key_value(m, 7, kv);
+            }
+        });
+    }
+}
+
+fn encode_metric(buf: &mut Vec<u8>, metric: &Metric) {
+    wire::write_bytes(buf, 1, metric.name.as_bytes());
+    wire::write_bytes(buf, 2, metric.description.as_bytes());
+    match metric.data {
+        Data::Gauge(ref points) => {
+            wire::write_message(buf, 5, |g| encode_number_points(g, points));
+        }
+        Data::Sum {
+            ref data_points,
+            is_monotonic,
+        } => {
+            wire::write_message(buf, 7, |s| {
+                encode_number_points(s, data_points);
+                wire::write_uint(s, 2, CUMULATIVE);
+                wire::write_uint(s, 3, u64::from(is_monotonic));
+            });
+        }
+        Data::Histogram(ref points) => {
+            wire::write_message(buf, 9, |h| {
+                for p in points {
+                    wire::write_message(h, 1, |m| {
+                        wire::write_fixed64(m, 2, p.start_time_unix_nano);
+                        wire::write_fixed64(m, 3, p.time_unix_nano);
+                        wire::write_fixed64(m, 4, p.count);
+                        wire::write_double(m, 5, p.sum);
+                        let counts: Vec<u8> = p
+                            .bucket_counts
+                            .iter()
+                            .flat_map(|c| c.to_le_bytes().to_vec())
+                            .collect();
+                        wire::write_bytes(m, 6, &counts);
+                        let bounds: Vec<u8> = p
+                            .explicit_bounds
+                            .iter()
+                            .flat_map(|b| b.to_le_bytes().to_vec())
+                            .collect();
+                        wire::write_bytes(m, 7, &bounds);
+                        for kv in &p.attributes {
+                            write_key_value(m, 9, kv);
+                        }
+                    });
+                }
+                wire::write_uint(h, 2, CUMULATIVE);
+            });
+        }
+        Data::Summary(ref points) => {
+            wire::write_message(buf, 11, |s| {
+                for p in points {
+                    wire::write_message(s, 1, |m| {
+                        wire::write_fixed64(m, 2, p.start_time_unix_nano);
+                        wire::write_fixed64(m, 3, p.time_unix_nano);
+                        wire::write_fixed64(m, 4, p.count);
+                        wire::write_double(m, 5, p.sum);
+                        for q in &p.quantile_values {
+                            wire::write_message(m, 6, |v| {
+                                wire::write_double(v, 1, q.quantile);
+                                wire::write_double(v, 2, q.value);
+                            });
+                        }
+                        for kv in &p.attributes {
+                            write_key_value(m, 7, kv);
+                        }
+                    });
+                }
+            });
+        }
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    let d = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    d.as_secs() * 1_000_000_000 + u64::from(d.subsec_nanos())
+}
+
+/// A builder of a background thread that periodically exports the metrics
+/// of a [`Registry`] to an OTLP/HTTP receiver.
+///
+/// Since all data points are cumulative, a failed export loses no data, the
+/// next one carries it, so failures are only counted.
+///
+/// # Examples
+///
+/// ```no_run
+/// use prometheus::otlp::OtlpExporter;
+///
+/// let handle = OtlpExporter::new("http://localhost:4318/v1/metrics")
+///     .resource_attribute("service.name", "frontend")
+///     .start()
+///     .unwrap();
+///
+/// // Export the final values before exiting.
+/// handle.shutdown().unwrap();
+/// ```
+pub struct OtlpExporter {
+    url: String,
+    registry: Registry,
+    resource: Vec<KeyValue>,
+    interval: Duration,
+    timeout: Duration,
+    headers: Vec<(String, String)>,
+    start_time_unix_nano: u64,
+    errors: IntCounter,
+    last_success: Gauge,
+}
+
+impl OtlpExporter {
+    /// Create an [`OtlpExporter`] that exports the metrics of the default
+    /// registry to `url` every 60 seconds. The start time of the data points
+    /// without a created timestamp is the time of this call.
+    pub fn new<S: Into<String>>(url: S) -> OtlpExporter {
+        OtlpExporter {
+            url: url.into(),
+            registry: default_registry().clone(),
+            resource: Vec::new(),
+            interval: DEFAULT_INTERVAL,
+            timeout: DEFAULT_TIMEOUT,
+            headers: Vec::new(),
+            start_time_unix_nano: unix_nanos(SystemTime::now()),
+            errors: IntCounter::new(
+                "prometheus_otlp_failed_exports_total",
+                "Number of failed OTLP exports.",
+            )
+            .unwrap(),
+            last_success: Gauge::new(
+                "prometheus_otlp_last_success_timestamp_seconds",
+                "Unix time of the last successful OTLP export.",
+            )
+            .unwrap(),
+        }
+    }
+
+    /// Export the metrics of `registry` instead of the default registry.
+    pub fn registry(mut self, registry: Registry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Add the attribute `key` to the resource, e.g. `service.name`.
+    pub fn resource_attribute<S1: Into<String>, S2: Into<String>>(
+        mut self,
+        key: S1,
+        value: S2,
+    ) -> Self {
+        self.resource.push(KeyValue::new(key, value));
+        self
+    }
+
+    /// Set the time between two exports.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the timeout of each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send the header `name: value` with every request, e.g. an API key.
+    pub fn header<S1: Into<String>, S2: Into<String>>(mut self, name: S1, value: S2) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Return the counter of failed exports. It is not registered anywhere,
+    /// register it to export or expose it.
+    pub fn errors(&self) -> &IntCounter {
+        &self.errors
+    }
+
+    /// Return the gauge holding the Unix time of the last successful export.
+    pub fn last_success(&self) -> &Gauge {
+        &self.last_success
+    }
+
+    /// Return the request the exporter would send now.
+    pub fn request(&self) -> ExportMetricsRequest {
+        let mut request = ExportMetricsRequest::from_metric_families(
+            &self.registry.gather(),
+            self.start_time_unix_nano,
+            unix_nanos(SystemTime::now()),
+        );
+        request.resource = self.resource.clone();
+        request
+    }
+
+    /// Start exporting on a background thread. The registry is first
+    /// exported after one interval.
+    pub fn start(self) -> Result<OtlpExportHandle> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Error::Msg(format!("invalid header name {}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| Error::Msg(format!("invalid value of header {}: {}", name, e)))?;
+            headers.append(name, value);
+        }
+        let client = Client::builder()
+            .timeout(self.timeout)
+            .default_headers(headers)
+            .build()
+            .map_err(|e| Error::Msg(format!("{}", e)))?;
+
+        let worker = Arc::new(Worker {
+            config: self,
+            client,
+        });
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let w = worker.clone();
+        let thread = thread::Builder::new()
+            .name("prometheus otlp".to_owned())
+            .spawn(move || loop {
+                match stopped.recv_timeout(w.config.interval) {
+                    // Failures are counted, the next export carries the data.
+                    Err(RecvTimeoutError::Timeout) => {
+                        let _ = w.export();
+                    }
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => return w.export(),
+                }
+            })
+            .map_err(|e: io::Error| Error::Msg(format!("{}", e)))?;
+
+        Ok(OtlpExportHandle {
+            worker,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl fmt::Debug for OtlpExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtlpExporter")
+            .field("url", &self.url)
+            .field("resource", &self.resource)
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+struct Worker {
+    config: OtlpExporter,
+    client: Client,
+}
+
+impl Worker {
+    fn export(&self) -> Result<()> {
+        let body = self.config.request().encode();
+        let res = self
+            .client
+            .post(&self.config.url)
+            .header(CONTENT_TYPE, "application/x-protobuf")
+            .body(body)
+            .send();
+        let err = match res {
+            Ok(ref response) if response.status().is_success() => {
+                self.config.last_success.set_to_current_time();
+                return Ok(());
+            }
+            Ok(response) => Error::Msg(format!(
+                "unexpected status code {} while exporting to {}",
+                response.status(),
+                self.config.url
+            )),
+            Err(e) => Error::Msg(format!("{}", e)),
+        };
+        self.config.errors.inc();
+        Err(err)
+    }
+}
+
+/// A handle to the background thread started by [`OtlpExporter::start`].
+///
+/// Dropping the handle shuts the thread down, ignoring the result of the
+/// final export.
+pub struct OtlpExportHandle {
+    worker: Arc<Worker>,
+    stop: Sender<()>,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl OtlpExportHandle {
+    /// Return the [`OtlpExporter`] run by the thread, e.g. to access its
+    /// metrics.
+    pub fn exporter(&self) -> &OtlpExporter {
+        &self.worker.config
+    }
+
+    /// Stop the thread after a final export, and return the result of that
+    /// export.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop_and_join()
+    }
+
+    fn stop_and_join(&mut self) -> Result<()> {
+        let thread = match self.thread.take() {
+            Some(thread) => thread,
+            None => return Ok(()),
+        };
+        let _ = self.stop.send(());
+        thread
+            .join()
+            .unwrap_or_else(|_| Err(Error::Msg("otlp thread panicked".to_owned())))
+    }
+}
+
+impl Drop for OtlpExportHandle {
+    fn drop(&mut self) {
+        let _ = self.stop_and_join();
+    }
+}
+
+impl fmt::Debug for OtlpExportHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtlpExportHandle")
+            .field("exporter", &self.worker.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::CounterVec;
+    use crate::gauge_histogram::GaugeHistogramVec;
+    use crate::histogram::{Histogram, HistogramOpts};
+    use crate::metrics::{Collector, Opts};
+    use crate::test_http::serve;
+
+    #[test]
+    fn test_convert() {
+        let registry = Registry::new();
+        let counter = CounterVec::new(Opts::new("test_counter", "test help"), &["a"]).unwrap();
+        counter.with_label_values(&["x"]).inc_by(2.0);
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("test_histogram", "test help").buckets(vec![1.0, 2.0]),
+        )
+        .unwrap();
+        histogram.observe(0.5);
+        histogram.observe(1.5);
+        histogram.observe(10.0);
+        registry.register(Box::new(counter)).unwrap();
+        registry.register(Box::new(histogram)).unwrap();
+
+        let request = ExportMetricsRequest::from_metric_families(&registry.gather(), 10, 20);
+        assert_eq!(request.metrics.len(), 2);
+        let points = match request.metrics[0].data {
+            Data::Sum {
+                ref data_points,
+                is_monotonic: true,
+            } => data_points,
+            ref data => panic!("unexpected data {:?}", data),
+        };
+        assert_eq!(points[0].attributes, vec![KeyValue::new("a", "x")]);
+        assert_eq!(points[0].time_unix_nano, 20);
+        assert_eq!(points[0].value, 2.0);
+        // Counters start at their created timestamp.
+        assert!(points[0].start_time_unix_nano > 20);
+
+        let point = match request.metrics[1].data {
+            Data::Histogram(ref points) => points[0].clone(),
+            ref data => panic!("unexpected data {:?}", data),
+        };
+        assert_eq!(point.explicit_bounds, vec![1.0, 2.0]);
+        assert_eq!(point.bucket_counts, vec![1, 1, 1]);
+        assert_eq!(point.count, 3);
+        assert_eq!(point.sum, 12.0);
+        assert!(point.start_time_unix_nano > 20);
+    }
+
+    #[test]
+    fn test_convert_gauge_histogram() {
+        let gh = GaugeHistogramVec::new(
+            HistogramOpts::new("test_gauge_histogram", "test help").buckets(vec![1.0]),
+            &["a"],
+        )
+        .unwrap();
+        gh.with_label_values(&["x"]).set(&[0.5, 2.0]);
+
+        let request = ExportMetricsRequest::from_metric_families(&gh.collect(), 10, 20);
+        let metrics: Vec<_> = request
+            .metrics
+            .iter()
+            .map(|metric| match metric.data {
+                Data::Gauge(ref points) => (metric.name.as_str(), points.clone()),
+                ref data => panic!("unexpected data {:?}", data),
+            })
+            .collect();
+        assert_eq!(metrics.len(), 3);
+
+        let (name, ref points) = metrics[0];
+        assert_eq!(name, "test_gauge_histogram_bucket");
+        let buckets: Vec<_> = points
+            .iter()
+            .map(|p| (p.attributes.clone(), p.value))
+            .collect();
+        assert_eq!(
+            buckets,
+            vec![
+                (vec![KeyValue::new("a", "x"), KeyValue::new("le", "1")], 1.0),
+                (
+                    vec![KeyValue::new("a", "x"), KeyValue::new("le", "+Inf")],
+                    2.0
+                ),
+            ]
+        );
+        assert_eq!(points[0].time_unix_nano, 20);
+
+        let (name, ref points) = metrics[1];
+        assert_eq!(name, "test_gauge_histogram_gcount");
+        assert_eq!(points[0].attributes, vec![KeyValue::new("a", "x")]);
+        assert_eq!(points[0].value, 2.0);
+        let (name, ref points) = metrics[2];
+        assert_eq!(name, "test_gauge_histogram_gsum");
+        assert_eq!(points[0].value, 2.5);
+    }
+
+    #[test]
+    fn test_encode() {
+        let request = ExportMetricsRequest {
+            resource: vec![KeyValue::new("k", "v")],
+            metrics: vec![Metric {
+                name: "g".to_owned(),
+                description: String::new(),
+                data: Data::Gauge(vec![NumberDataPoint {
+                    attributes: vec![],
+                    start_time_unix_nano: 1,
+                    time_unix_nano: 2,
+                    value: 1.0,
+                }]),
+            }],
+        };
+        let buf = request.encode();
+
+        let scope = format!(
+            "\x0a{}{}\x12{}{}",
+            env!("CARGO_PKG_NAME").len() as u8 as char,
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION").len() as u8 as char,
+            env!("CARGO_PKG_VERSION")
+        );
+        let mut point = vec![
+            0x11, 1, 0, 0, 0, 0, 0, 0, 0, 0x19, 2, 0, 0, 0, 0, 0, 0, 0, 0x21,
+        ];
+        point.extend_from_slice(&1.0f64.to_le_bytes());
+        let mut metric = vec![0x0a, 1, b'g', 0x12, 0, 0x2a, 29, 0x0a, 27];
+        metric.extend_from_slice(&point);
+        let mut sm = vec![0x0a, scope.len() as u8];
+        sm.extend_from_slice(scope.as_bytes());
+        sm.extend_from_slice(&[0x12, metric.len() as u8]);
+        sm.extend_from_slice(&metric);
+        let resource = [0x0a, 10, 0x0a, 8, 0x0a, 1, b'k', 0x12, 3, 0x0a, 1, b'v'];
+        let mut rm = resource.to_vec();
+        rm.extend_from_slice(&[0x12, sm.len() as u8]);
+        rm.extend_from_slice(&sm);
+        let mut expected = vec![0x0a, rm.len() as u8];
+        expected.extend_from_slice(&rm);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_exporter() {
+        let (addr, server) = serve(2, 500);
+
+        let registry = Registry::new();
+        let counter = IntCounter::new("test_counter", "test help").unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+
+        let handle = OtlpExporter::new(format!("http://{}/v1/metrics", addr))
+            .registry(registry)
+            .resource_attribute("service.name", "test")
+            .header("api-key", "secret")
+            .interval(Duration::from_millis(10))
+            .start()
+            .unwrap();
+        while handle.exporter().errors().get() < 1 {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(handle.shutdown().is_err());
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].line, "POST /v1/metrics HTTP/1.1");
+        assert_eq!(
+            requests[0].header("content-type"),
+            Some("application/x-protobuf")
+        );
+        assert_eq!(requests[0].header("api-key"), Some("secret"));
+        let body = &requests[0].body;
+        assert!(body.windows(12).any(|w| w == b"test_counter"));
+        assert!(body.windows(12).any(|w| w == b"service.name"));
+    }
+}
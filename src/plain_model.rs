@@ -6,6 +6,7 @@
 #![allow(missing_docs)]
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LabelPair {
     name: String,
     value: String,
@@ -43,6 +44,7 @@ impl LabelPair {
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gauge {
     value: f64,
 }
@@ -63,8 +65,40 @@ impl Gauge {
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp {
+    seconds: i64,
+    nanos: i32,
+}
+
+static DEFAULT_TIMESTAMP: Timestamp = Timestamp {
+    seconds: 0,
+    nanos: 0,
+};
+
+impl Timestamp {
+    pub fn set_seconds(&mut self, v: i64) {
+        self.seconds = v;
+    }
+
+    pub fn get_seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    pub fn set_nanos(&mut self, v: i32) {
+        self.nanos = v;
+    }
+
+    pub fn get_nanos(&self) -> i32 {
+        self.nanos
+    }
+}
+
+#[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Counter {
     value: f64,
+    created_timestamp: Option<Timestamp>,
 }
 
 impl Counter {
@@ -81,9 +115,31 @@ impl Counter {
     pub fn get_value(&self) -> f64 {
         self.value
     }
+    pub fn has_created_timestamp(&self) -> bool {
+        self.created_timestamp.is_some()
+    }
+
+    pub fn set_created_timestamp(&mut self, v: Timestamp) {
+        self.created_timestamp = Some(v);
+    }
+
+    pub fn mut_created_timestamp(&mut self) -> &mut Timestamp {
+        self.created_timestamp.get_or_insert_with(Default::default)
+    }
+
+    pub fn clear_created_timestamp(&mut self) {
+        self.created_timestamp = None;
+    }
+
+    pub fn get_created_timestamp(&self) -> &Timestamp {
+        self.created_timestamp
+            .as_ref()
+            .unwrap_or(&DEFAULT_TIMESTAMP)
+    }
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quantile {
     quantile: f64,
     value: f64,
@@ -113,10 +169,12 @@ impl Quantile {
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Summary {
     sample_count: u64,
     sample_sum: f64,
     quantile: Vec<Quantile>,
+    created_timestamp: Option<Timestamp>,
 }
 
 impl Summary {
@@ -148,9 +206,31 @@ impl Summary {
     pub fn get_quantile(&self) -> &[Quantile] {
         &self.quantile
     }
+    pub fn has_created_timestamp(&self) -> bool {
+        self.created_timestamp.is_some()
+    }
+
+    pub fn set_created_timestamp(&mut self, v: Timestamp) {
+        self.created_timestamp = Some(v);
+    }
+
+    pub fn mut_created_timestamp(&mut self) -> &mut Timestamp {
+        self.created_timestamp.get_or_insert_with(Default::default)
+    }
+
+    pub fn clear_created_timestamp(&mut self) {
+        self.created_timestamp = None;
+    }
+
+    pub fn get_created_timestamp(&self) -> &Timestamp {
+        self.created_timestamp
+            .as_ref()
+            .unwrap_or(&DEFAULT_TIMESTAMP)
+    }
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Untyped {
     value: f64,
 }
@@ -179,10 +259,12 @@ impl Untyped {
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Histogram {
     sample_count: u64,
     sample_sum: f64,
     bucket: Vec<Bucket>,
+    created_timestamp: Option<Timestamp>,
 }
 
 impl Histogram {
@@ -214,9 +296,31 @@ impl Histogram {
     pub fn get_bucket(&self) -> &[Bucket] {
         &self.bucket
     }
+    pub fn has_created_timestamp(&self) -> bool {
+        self.created_timestamp.is_some()
+    }
+
+    pub fn set_created_timestamp(&mut self, v: Timestamp) {
+        self.created_timestamp = Some(v);
+    }
+
+    pub fn mut_created_timestamp(&mut self) -> &mut Timestamp {
+        self.created_timestamp.get_or_insert_with(Default::default)
+    }
+
+    pub fn clear_created_timestamp(&mut self) {
+        self.created_timestamp = None;
+    }
+
+    pub fn get_created_timestamp(&self) -> &Timestamp {
+        self.created_timestamp
+            .as_ref()
+            .unwrap_or(&DEFAULT_TIMESTAMP)
+    }
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bucket {
     cumulative_count: u64,
     upper_bound: f64,
@@ -246,6 +350,7 @@ impl Bucket {
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Metric {
     // message fields
     label: Vec<LabelPair>,
@@ -336,13 +441,16 @@ impl Metric {
     }
 }
 
+#[allow(non_camel_case_types)]
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MetricType {
     COUNTER,
     GAUGE,
     SUMMARY,
     UNTYPED,
     HISTOGRAM,
+    GAUGE_HISTOGRAM,
 }
 
 impl Default for MetricType {
@@ -352,6 +460,7 @@ impl Default for MetricType {
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MetricFamily {
     name: String,
     help: String,
@@ -3,6 +3,7 @@
 
 use std::collections::HashMap;
 use std::hash::BuildHasher;
+use std::io::Write;
 use std::str::{self, FromStr};
 use std::time::Duration;
 
@@ -10,13 +11,16 @@ use reqwest::blocking::Client;
 use reqwest::header::CONTENT_TYPE;
 use reqwest::{Method, StatusCode, Url};
 
-use crate::encoder::{Encoder, ProtobufEncoder};
+use crate::desc::is_valid_label_name;
+use crate::encoder::{Encoder, TextEncoder, TEXT_FORMAT};
+#[cfg(feature = "protobuf")]
+use crate::encoder::{ProtobufEncoder, PROTOBUF_FORMAT};
 use crate::errors::{Error, Result};
 use crate::metrics::Collector;
 use crate::proto;
 use crate::registry::Registry;
 
-const REQWEST_TIMEOUT_SEC: Duration = Duration::from_secs(10);
+pub(crate) const REQWEST_TIMEOUT_SEC: Duration = Duration::from_secs(10);
 
 lazy_static! {
     static ref HTTP_CLIENT: Client = Client::builder()
@@ -25,10 +29,34 @@ lazy_static! {
         .unwrap();
 }
 
+/// The wire format of pushed metrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PushFormat {
+    /// The protobuf format, which is the default if the `protobuf` feature is
+    /// enabled.
+    #[cfg(feature = "protobuf")]
+    Protobuf,
+    /// The text format, which is the default otherwise. Some receivers
+    /// compatible with the Pushgateway only accept this format.
+    Text,
+}
+
+impl Default for PushFormat {
+    #[cfg(feature = "protobuf")]
+    fn default() -> PushFormat {
+        PushFormat::Protobuf
+    }
+
+    #[cfg(not(feature = "protobuf"))]
+    fn default() -> PushFormat {
+        PushFormat::Text
+    }
+}
+
 /// `BasicAuthentication` holder for supporting `push` to Pushgateway endpoints
 /// using Basic access authentication.
 /// Can be passed to any `push_metrics` method.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct BasicAuthentication {
     /// The Basic Authentication username (possibly empty string).
     pub username: String,
@@ -39,9 +67,10 @@ pub struct BasicAuthentication {
 /// `push_metrics` pushes all gathered metrics to the Pushgateway specified by
 /// url, using the provided job name and the (optional) further grouping labels
 /// (the grouping map may be nil). See the Pushgateway documentation for
-/// detailed implications of the job and other grouping labels. Neither the job
-/// name nor any grouping label value may contain a "/". The metrics pushed must
-/// not contain a job label of their own nor any of the grouping labels.
+/// detailed implications of the job and other grouping labels. The job name
+/// must not be empty, values containing "/" or other special characters are
+/// base64 encoded in the URL. The metrics pushed must not contain a job label
+/// of their own nor any of the grouping labels.
 ///
 /// You can use just host:port or ip:port as url, in which case 'http://' is
 /// added automatically. You can also include the schema in the URL. However, do
@@ -73,6 +102,19 @@ pub fn push_add_metrics<S: BuildHasher>(
     push(job, grouping, url, mfs, "POST", basic_auth)
 }
 
+/// `delete_metrics` deletes all metrics pushed with the given job and
+/// grouping labels from the Pushgateway, e.g. when the job has finished and
+/// its metrics should not be exposed anymore. Metrics pushed with other
+/// grouping labels are kept. (It uses HTTP method 'DELETE'.)
+pub fn delete_metrics<S: BuildHasher>(
+    job: &str,
+    grouping: HashMap<String, String, S>,
+    url: &str,
+    basic_auth: Option<BasicAuthentication>,
+) -> Result<()> {
+    push(job, grouping, url, Vec::new(), "DELETE", basic_auth)
+}
+
 const LABEL_NAME_JOB: &str = "job";
 
 fn push<S: BuildHasher>(
@@ -83,94 +125,167 @@ fn push<S: BuildHasher>(
     method: &str,
     basic_auth: Option<BasicAuthentication>,
 ) -> Result<()> {
-    // Suppress clippy warning needless_pass_by_value.
-    let grouping = grouping;
+    let request = PushRequest::new(job, &grouping, url, mfs, PushFormat::default(), false)?;
 
-    let mut push_url = if url.contains("://") {
-        url.to_owned()
-    } else {
-        format!("http://{}", url)
-    };
+    let mut builder = HTTP_CLIENT
+        .request(Method::from_str(method).unwrap(), request.url.clone())
+        .header(CONTENT_TYPE, request.content_type)
+        .body(request.body);
 
-    if push_url.ends_with('/') {
-        push_url.pop();
+    if let Some(BasicAuthentication { username, password }) = basic_auth {
+        builder = builder.basic_auth(username, Some(password));
     }
 
-    let mut url_components = Vec::new();
-    if job.contains('/') {
-        return Err(Error::Msg(format!("job contains '/': {}", job)));
-    }
+    let response = builder.send().map_err(|e| Error::Msg(format!("{}", e)))?;
+    check_status(response.status(), &request.url)
+}
 
-    // TODO: escape job
-    url_components.push(job.to_owned());
+/// The URL and the encoded body of a push, shared by the blocking and the
+/// asynchronous clients.
+pub(crate) struct PushRequest {
+    pub url: Url,
+    pub content_type: &'static str,
+    pub content_encoding: Option<&'static str>,
+    pub body: Vec<u8>,
+}
 
-    for (ln, lv) in &grouping {
-        // TODO: check label name
-        if lv.contains('/') {
-            return Err(Error::Msg(format!(
-                "value of grouping label {} contains '/': {}",
-                ln, lv
-            )));
+impl PushRequest {
+    pub(crate) fn new<S: BuildHasher>(
+        job: &str,
+        grouping: &HashMap<String, String, S>,
+        url: &str,
+        mfs: Vec<proto::MetricFamily>,
+        format: PushFormat,
+        gzip: bool,
+    ) -> Result<PushRequest> {
+        let mut push_url = if url.contains("://") {
+            url.to_owned()
+        } else {
+            format!("http://{}", url)
+        };
+
+        if push_url.ends_with('/') {
+            push_url.pop();
         }
-        url_components.push(ln.to_owned());
-        url_components.push(lv.to_owned());
-    }
 
-    push_url = format!("{}/metrics/job/{}", push_url, url_components.join("/"));
-
-    let encoder = ProtobufEncoder::new();
-    let mut buf = Vec::new();
-
-    for mf in mfs {
-        // Check for pre-existing grouping labels:
-        for m in mf.get_metric() {
-            for lp in m.get_label() {
-                if lp.get_name() == LABEL_NAME_JOB {
-                    return Err(Error::Msg(format!(
-                        "pushed metric {} already contains a \
-                         job label",
-                        mf.get_name()
-                    )));
-                }
-                if grouping.contains_key(lp.get_name()) {
-                    return Err(Error::Msg(format!(
-                        "pushed metric {} already contains \
-                         grouping label {}",
-                        mf.get_name(),
-                        lp.get_name()
-                    )));
+        if job.is_empty() {
+            return Err(Error::Msg("job must not be empty".to_owned()));
+        }
+        let mut url_components = vec![encode_value(LABEL_NAME_JOB, job)];
+
+        // Sort the labels so that the same grouping always maps to the same URL.
+        let mut labels: Vec<_> = grouping.iter().collect();
+        labels.sort();
+        for (ln, lv) in labels {
+            if !is_valid_label_name(ln) {
+                return Err(Error::Msg(format!("invalid grouping label name: {}", ln)));
+            }
+            if ln == LABEL_NAME_JOB {
+                return Err(Error::Msg(
+                    "job is not allowed as grouping label, it is set by the job argument"
+                        .to_owned(),
+                ));
+            }
+            url_components.push(encode_value(ln, lv));
+        }
+
+        push_url = format!("{}/metrics/{}", push_url, url_components.join("/"));
+
+        let mut buf = Vec::new();
+
+        for mf in mfs {
+            // Check for pre-existing grouping labels:
+            for m in mf.get_metric() {
+                for lp in m.get_label() {
+                    if lp.get_name() == LABEL_NAME_JOB {
+                        return Err(Error::Msg(format!(
+                            "pushed metric {} already contains a \
+                             job label",
+                            mf.get_name()
+                        )));
+                    }
+                    if grouping.contains_key(lp.get_name()) {
+                        return Err(Error::Msg(format!(
+                            "pushed metric {} already contains \
+                             grouping label {}",
+                            mf.get_name(),
+                            lp.get_name()
+                        )));
+                    }
                 }
             }
+            // Ignore error, `no metrics` and `no name`.
+            let _ = match format {
+                #[cfg(feature = "protobuf")]
+                PushFormat::Protobuf => ProtobufEncoder::new().encode(&[mf], &mut buf),
+                PushFormat::Text => TextEncoder::new().encode(&[mf], &mut buf),
+            };
         }
-        // Ignore error, `no metrics` and `no name`.
-        let _ = encoder.encode(&[mf], &mut buf);
+
+        let content_encoding = if gzip {
+            let mut encoder = libflate::gzip::Encoder::new(Vec::new())?;
+            encoder.write_all(&buf)?;
+            buf = encoder.finish().into_result()?;
+            Some("gzip")
+        } else {
+            None
+        };
+
+        let url = Url::from_str(&push_url)
+            .map_err(|e| Error::Msg(format!("invalid push url {}: {}", push_url, e)))?;
+        Ok(PushRequest {
+            url,
+            content_type: match format {
+                #[cfg(feature = "protobuf")]
+                PushFormat::Protobuf => PROTOBUF_FORMAT,
+                PushFormat::Text => TEXT_FORMAT,
+            },
+            content_encoding,
+            body: buf,
+        })
     }
+}
 
-    let mut builder = HTTP_CLIENT
-        .request(
-            Method::from_str(method).unwrap(),
-            Url::from_str(&push_url).unwrap(),
+/// Returns the `name/value` path segments of a grouping label. Values that
+/// would not survive as a single path segment, including empty values, use the
+/// URL-safe base64 encoding of the Pushgateway, as `name@base64/encoded`.
+fn encode_value(name: &str, value: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-._~".contains(c);
+    if value.is_empty() {
+        // An empty encoded value would be an empty path segment.
+        format!("{}@base64/=", name)
+    } else if value.chars().all(plain) && value != "." && value != ".." {
+        format!("{}/{}", name, value)
+    } else {
+        format!(
+            "{}@base64/{}",
+            name,
+            base64::encode_config(value, base64::URL_SAFE)
         )
-        .header(CONTENT_TYPE, encoder.format_type())
-        .body(buf);
-
-    if let Some(BasicAuthentication { username, password }) = basic_auth {
-        builder = builder.basic_auth(username, Some(password));
     }
+}
 
-    let response = builder.send().map_err(|e| Error::Msg(format!("{}", e)))?;
-
-    match response.status() {
+pub(crate) fn check_status(status: StatusCode, url: &Url) -> Result<()> {
+    match status {
         StatusCode::ACCEPTED => Ok(()),
         StatusCode::OK => Ok(()),
         _ => Err(Error::Msg(format!(
             "unexpected status code {} while pushing to {}",
-            response.status(),
-            push_url
+            status, url
         ))),
     }
 }
 
+pub(crate) fn gather_collectors(
+    collectors: Vec<Box<dyn Collector>>,
+) -> Result<Vec<proto::MetricFamily>> {
+    let registry = Registry::new();
+    for bc in collectors {
+        registry.register(bc)?;
+    }
+    Ok(registry.gather())
+}
+
 fn push_from_collector<S: BuildHasher>(
     job: &str,
     grouping: HashMap<String, String, S>,
@@ -179,12 +294,7 @@ fn push_from_collector<S: BuildHasher>(
     method: &str,
     basic_auth: Option<BasicAuthentication>,
 ) -> Result<()> {
-    let registry = Registry::new();
-    for bc in collectors {
-        registry.register(bc)?;
-    }
-
-    let mfs = registry.gather();
+    let mfs = gather_collectors(collectors)?;
     push(job, grouping, url, mfs, method, basic_auth)
 }
 
@@ -212,6 +322,107 @@ pub fn push_add_collector<S: BuildHasher>(
     push_from_collector(job, grouping, url, collectors, "POST", basic_auth)
 }
 
+#[cfg(feature = "push-async")]
+lazy_static! {
+    static ref ASYNC_HTTP_CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(REQWEST_TIMEOUT_SEC)
+        .build()
+        .unwrap();
+}
+
+/// `push_metrics_async` works like `push_metrics`, but does not block the
+/// calling thread, so it can be awaited from within a tokio runtime.
+#[cfg(feature = "push-async")]
+pub async fn push_metrics_async<S: BuildHasher>(
+    job: &str,
+    grouping: HashMap<String, String, S>,
+    url: &str,
+    mfs: Vec<proto::MetricFamily>,
+    basic_auth: Option<BasicAuthentication>,
+) -> Result<()> {
+    push_async(job, grouping, url, mfs, "PUT", basic_auth).await
+}
+
+/// `push_add_metrics_async` works like `push_add_metrics`, but does not block
+/// the calling thread.
+#[cfg(feature = "push-async")]
+pub async fn push_add_metrics_async<S: BuildHasher>(
+    job: &str,
+    grouping: HashMap<String, String, S>,
+    url: &str,
+    mfs: Vec<proto::MetricFamily>,
+    basic_auth: Option<BasicAuthentication>,
+) -> Result<()> {
+    push_async(job, grouping, url, mfs, "POST", basic_auth).await
+}
+
+/// `push_collector_async` works like `push_collector`, but does not block the
+/// calling thread.
+#[cfg(feature = "push-async")]
+pub async fn push_collector_async<S: BuildHasher>(
+    job: &str,
+    grouping: HashMap<String, String, S>,
+    url: &str,
+    collectors: Vec<Box<dyn Collector>>,
+    basic_auth: Option<BasicAuthentication>,
+) -> Result<()> {
+    let mfs = gather_collectors(collectors)?;
+    push_async(job, grouping, url, mfs, "PUT", basic_auth).await
+}
+
+/// `push_add_collector_async` works like `push_add_collector`, but does not
+/// block the calling thread.
+#[cfg(feature = "push-async")]
+pub async fn push_add_collector_async<S: BuildHasher>(
+    job: &str,
+    grouping: HashMap<String, String, S>,
+    url: &str,
+    collectors: Vec<Box<dyn Collector>>,
+    basic_auth: Option<BasicAuthentication>,
+) -> Result<()> {
+    let mfs = gather_collectors(collectors)?;
+    push_async(job, grouping, url, mfs, "POST", basic_auth).await
+}
+
+/// `delete_metrics_async` works like `delete_metrics`, but does not block the
+/// calling thread.
+#[cfg(feature = "push-async")]
+pub async fn delete_metrics_async<S: BuildHasher>(
+    job: &str,
+    grouping: HashMap<String, String, S>,
+    url: &str,
+    basic_auth: Option<BasicAuthentication>,
+) -> Result<()> {
+    push_async(job, grouping, url, Vec::new(), "DELETE", basic_auth).await
+}
+
+#[cfg(feature = "push-async")]
+async fn push_async<S: BuildHasher>(
+    job: &str,
+    grouping: HashMap<String, String, S>,
+    url: &str,
+    mfs: Vec<proto::MetricFamily>,
+    method: &str,
+    basic_auth: Option<BasicAuthentication>,
+) -> Result<()> {
+    let request = PushRequest::new(job, &grouping, url, mfs, PushFormat::default(), false)?;
+
+    let mut builder = ASYNC_HTTP_CLIENT
+        .request(Method::from_str(method).unwrap(), request.url.clone())
+        .header(CONTENT_TYPE, request.content_type)
+        .body(request.body);
+
+    if let Some(BasicAuthentication { username, password }) = basic_auth {
+        builder = builder.basic_auth(username, Some(password));
+    }
+
+    let response = builder
+        .send()
+        .await
+        .map_err(|e| Error::Msg(format!("{}", e)))?;
+    check_status(response.status(), &request.url)
+}
+
 const DEFAULT_GROUP_LABEL_PAIR: (&str, &str) = ("instance", "unknown");
 
 /// `hostname_grouping_key` returns a label map with the only entry
@@ -255,6 +466,7 @@ pub fn hostname_grouping_key() -> HashMap<String, String> {
 mod tests {
     use super::*;
     use crate::proto;
+    use crate::test_http::serve;
 
     #[test]
     fn test_hostname_grouping_key() {
@@ -272,14 +484,81 @@ mod tests {
         ];
 
         for case in table {
-            let mut l = proto::LabelPair::new();
+            let mut l = proto::LabelPair::default();
             l.set_name(case.0.to_owned());
-            let mut m = proto::Metric::new();
+            let mut m = proto::Metric::default();
             m.set_label(from_vec!(vec![l]));
-            let mut mf = proto::MetricFamily::new();
+            let mut mf = proto::MetricFamily::default();
             mf.set_metric(from_vec!(vec![m]));
             let res = push_metrics("test", hostname_grouping_key(), "mockurl", vec![mf], None);
             assert!(format!("{}", res.unwrap_err()).contains(case.1));
         }
     }
+
+    #[test]
+    fn test_push_url() {
+        let mut grouping = HashMap::new();
+        grouping.insert("path".to_owned(), "/var/tmp".to_owned());
+        grouping.insert("empty".to_owned(), "".to_owned());
+        grouping.insert("instance".to_owned(), "host-1.example".to_owned());
+        grouping.insert("url".to_owned(), "http://a?b".to_owned());
+        let request = PushRequest::new(
+            "batch/job",
+            &grouping,
+            "localhost:9091/",
+            vec![],
+            PushFormat::default(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:9091/metrics/job@base64/YmF0Y2gvam9i\
+             /empty@base64/=/instance/host-1.example/path@base64/L3Zhci90bXA=\
+             /url@base64/aHR0cDovL2E_Yg=="
+        );
+
+        for name in &["job", "a-b", "", "0a"] {
+            let mut grouping = HashMap::new();
+            grouping.insert((*name).to_owned(), "x".to_owned());
+            let res = PushRequest::new(
+                "test",
+                &grouping,
+                "localhost",
+                vec![],
+                PushFormat::Text,
+                false,
+            );
+            assert!(res.is_err());
+        }
+    }
+
+    #[test]
+    fn test_delete_metrics() {
+        let (addr, server) = serve(1, 202);
+        let grouping = labels! {"instance".to_owned() => "a".to_owned(),};
+        delete_metrics("test", grouping, &addr, None).unwrap();
+
+        let requests = server.join().unwrap();
+        assert_eq!(
+            requests[0].line,
+            "DELETE /metrics/job/test/instance/a HTTP/1.1"
+        );
+        assert!(requests[0].body.is_empty());
+
+        let res = delete_metrics("", HashMap::new(), &addr, None);
+        assert!(format!("{}", res.unwrap_err()).contains("job must not be empty"));
+    }
+
+    #[cfg(feature = "push-async")]
+    #[tokio::test]
+    async fn test_push_async_bad_job() {
+        let res = push_metrics_async("", hostname_grouping_key(), "mockurl", vec![], None);
+        assert!(format!("{}", res.await.unwrap_err()).contains("job must not be empty"));
+
+        let mut grouping = hostname_grouping_key();
+        grouping.insert("a-b".to_owned(), "x".to_owned());
+        let res = push_add_collector_async("test", grouping, "mockurl", vec![], None);
+        assert!(format!("{}", res.await.unwrap_err()).contains("invalid grouping label name"));
+    }
 }
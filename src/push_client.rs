@@ -0,0 +1,415 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{Certificate, Identity, Method};
+
+use crate::backoff::next_backoff;
+use crate::errors::{Error, Result};
+use crate::metrics::Collector;
+use crate::proto;
+use crate::push::{
+    check_status, gather_collectors, BasicAuthentication, PushFormat, PushRequest,
+    REQWEST_TIMEOUT_SEC,
+};
+
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Options of a [`PushClient`], for Pushgateways behind authenticating
+/// proxies or with TLS client authentication.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use prometheus::{PushClient, PushOptions};
+///
+/// let client = PushClient::new(
+///     PushOptions::new()
+///         .header("X-Scope-OrgID", "batch")
+///         .bearer_token("secret")
+///         .timeout(Duration::from_secs(5))
+///         .retries(3, Duration::from_millis(200)),
+/// )
+/// .unwrap();
+/// # drop(client);
+/// ```
+#[derive(Clone)]
+pub struct PushOptions {
+    headers: Vec<(String, String)>,
+    basic_auth: Option<BasicAuthentication>,
+    bearer_token: Option<String>,
+    timeout: Duration,
+    ca_certificates: Vec<Vec<u8>>,
+    identity: Option<(Vec<u8>, String)>,
+    max_retries: u32,
+    retry_backoff: Duration,
+    format: PushFormat,
+    gzip: bool,
+}
+
+impl PushOptions {
+    /// Create [`PushOptions`] with a timeout of 10 seconds and no retries.
+    pub fn new() -> PushOptions {
+        PushOptions::default()
+    }
+
+    /// Send the header `name: value` with every request.
+    pub fn header<S1: Into<String>, S2: Into<String>>(mut self, name: S1, value: S2) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Authenticate with Basic access authentication.
+    pub fn basic_auth(mut self, basic_auth: BasicAuthentication) -> Self {
+        self.basic_auth = Some(basic_auth);
+        self
+    }
+
+    /// Authenticate with the bearer token `token`.
+    pub fn bearer_token<S: Into<String>>(mut self, token: S) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Set the timeout of each attempt of a request. Retries get a timeout of
+    /// their own, and the backoff between them is not included.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Trust the PEM encoded CA certificate `pem`, in addition to the system
+    /// certificates.
+    pub fn ca_certificate_pem(mut self, pem: Vec<u8>) -> Self {
+        self.ca_certificates.push(pem);
+        self
+    }
+
+    /// Authenticate with the client certificate and private key of the
+    /// PKCS #12 archive `der`, protected by `password`.
+    pub fn client_identity_pkcs12<S: Into<String>>(mut self, der: Vec<u8>, password: S) -> Self {
+        self.identity = Some((der, password.into()));
+        self
+    }
+
+    /// Encode the pushed metrics in `format`.
+    pub fn format(mut self, format: PushFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Compress the pushed metrics with gzip.
+    pub fn gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    /// Retry requests failing with a 5xx status code or without a response
+    /// up to `max_retries` times. The first retry waits `backoff`, which
+    /// doubles with every further retry, up to a minute.
+    pub fn retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+}
+
+impl Default for PushOptions {
+    fn default() -> PushOptions {
+        PushOptions {
+            headers: Vec::new(),
+            basic_auth: None,
+            bearer_token: None,
+            timeout: REQWEST_TIMEOUT_SEC,
+            ca_certificates: Vec::new(),
+            identity: None,
+            max_retries: 0,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            format: PushFormat::default(),
+            gzip: false,
+        }
+    }
+}
+
+impl fmt::Debug for PushOptions {
+    // Leaves out credentials.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PushOptions")
+            .field(
+                "headers",
+                &self.headers.iter().map(|(n, _)| n).collect::<Vec<_>>(),
+            )
+            .field("timeout", &self.timeout)
+            .field("max_retries", &self.max_retries)
+            .field("retry_backoff", &self.retry_backoff)
+            .field("format", &self.format)
+            .field("gzip", &self.gzip)
+            .finish()
+    }
+}
+
+/// A client pushing to a Pushgateway with custom [`PushOptions`].
+///
+/// Its methods work like the functions of the same name, e.g.
+/// [`push_metrics`](crate::push_metrics), with the authentication of the
+/// options.
+#[derive(Debug)]
+pub struct PushClient {
+    client: Client,
+    options: PushOptions,
+}
+
+impl PushClient {
+    /// Create a [`PushClient`], failing on invalid headers or certificates.
+    pub fn new(options: PushOptions) -> Result<PushClient> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &options.headers {
+            let name = HeaderName::from_str(name)
+                .map_err(|e| Error::Msg(format!("invalid header name {}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| Error::Msg(format!("invalid value of header {}: {}", name, e)))?;
+            headers.append(name, value);
+        }
+
+        let mut builder = Client::builder()
+            .timeout(options.timeout)
+            .default_headers(headers);
+        for pem in &options.ca_certificates {
+            let cert = Certificate::from_pem(pem)
+                .map_err(|e| Error::Msg(format!("invalid CA certificate: {}", e)))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        if let Some((ref der, ref password)) = options.identity {
+            let identity = Identity::from_pkcs12_der(der, password)
+                .map_err(|e| Error::Msg(format!("invalid client identity: {}", e)))?;
+            builder = builder.identity(identity);
+        }
+
+        let client = builder.build().map_err(|e| Error::Msg(format!("{}", e)))?;
+        Ok(PushClient { client, options })
+    }
+
+    /// Like [`push_metrics`](crate::push_metrics), replacing all metrics of
+    /// the group.
+    pub fn push_metrics<S: BuildHasher>(
+        &self,
+        job: &str,
+        grouping: HashMap<String, String, S>,
+        url: &str,
+        mfs: Vec<proto::MetricFamily>,
+    ) -> Result<()> {
+        self.send("PUT", self.request(job, &grouping, url, mfs)?)
+    }
+
+    /// Like [`push_add_metrics`](crate::push_add_metrics), replacing only the
+    /// metrics with the same names.
+    pub fn push_add_metrics<S: BuildHasher>(
+        &self,
+        job: &str,
+        grouping: HashMap<String, String, S>,
+        url: &str,
+        mfs: Vec<proto::MetricFamily>,
+    ) -> Result<()> {
+        self.send("POST", self.request(job, &grouping, url, mfs)?)
+    }
+
+    /// Like [`push_collector`](crate::push_collector).
+    pub fn push_collector<S: BuildHasher>(
+        &self,
+        job: &str,
+        grouping: HashMap<String, String, S>,
+        url: &str,
+        collectors: Vec<Box<dyn Collector>>,
+    ) -> Result<()> {
+        let mfs = gather_collectors(collectors)?;
+        self.push_metrics(job, grouping, url, mfs)
+    }
+
+    /// Like [`push_add_collector`](crate::push_add_collector).
+    pub fn push_add_collector<S: BuildHasher>(
+        &self,
+        job: &str,
+        grouping: HashMap<String, String, S>,
+        url: &str,
+        collectors: Vec<Box<dyn Collector>>,
+    ) -> Result<()> {
+        let mfs = gather_collectors(collectors)?;
+        self.push_add_metrics(job, grouping, url, mfs)
+    }
+
+    /// Like [`delete_metrics`](crate::delete_metrics).
+    pub fn delete_metrics<S: BuildHasher>(
+        &self,
+        job: &str,
+        grouping: HashMap<String, String, S>,
+        url: &str,
+    ) -> Result<()> {
+        self.send("DELETE", self.request(job, &grouping, url, Vec::new())?)
+    }
+
+    fn request<S: BuildHasher>(
+        &self,
+        job: &str,
+        grouping: &HashMap<String, String, S>,
+        url: &str,
+        mfs: Vec<proto::MetricFamily>,
+    ) -> Result<PushRequest> {
+        let PushOptions { format, gzip, .. } = self.options;
+        PushRequest::new(job, grouping, url, mfs, format, gzip)
+    }
+
+    fn send(&self, method: &str, request: PushRequest) -> Result<()> {
+        let mut backoff = self.options.retry_backoff;
+        let mut retries = 0;
+        loop {
+            let res = self.send_once(method, &request);
+            let retry = match res {
+                Ok(ref response) => response.status().is_server_error(),
+                Err(_) => true,
+            };
+            if !retry || retries >= self.options.max_retries {
+                let response = res.map_err(|e| Error::Msg(format!("{}", e)))?;
+                return check_status(response.status(), &request.url);
+            }
+
+            thread::sleep(backoff);
+            backoff = next_backoff(backoff);
+            retries += 1;
+        }
+    }
+
+    fn send_once(&self, method: &str, request: &PushRequest) -> reqwest::Result<Response> {
+        let mut builder = self
+            .client
+            .request(Method::from_str(method).unwrap(), request.url.clone())
+            .header(CONTENT_TYPE, request.content_type)
+            .body(request.body.clone());
+        if let Some(encoding) = request.content_encoding {
+            builder = builder.header(CONTENT_ENCODING, encoding);
+        }
+
+        if let Some(BasicAuthentication {
+            ref username,
+            ref password,
+        }) = self.options.basic_auth
+        {
+            builder = builder.basic_auth(username, Some(password));
+        }
+        if let Some(ref token) = self.options.bearer_token {
+            builder = builder.bearer_auth(token);
+        }
+
+        builder.send()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::counter::Counter;
+    use crate::encoder::TEXT_FORMAT;
+    use crate::test_http::{serve, serve_statuses};
+
+    #[test]
+    fn test_push_client_headers() {
+        let (addr, server) = serve(1, 200);
+        let client = PushClient::new(
+            PushOptions::new()
+                .header("X-Scope-OrgID", "test")
+                .bearer_token("secret"),
+        )
+        .unwrap();
+        client
+            .push_metrics("test", HashMap::new(), &addr, vec![])
+            .unwrap();
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].header("x-scope-orgid"), Some("test"));
+        assert_eq!(requests[0].header("authorization"), Some("Bearer secret"));
+
+        assert!(PushClient::new(PushOptions::new().header("in valid", "x")).is_err());
+        assert!(PushClient::new(PushOptions::new().header("x", "in\nvalid")).is_err());
+        assert!(PushClient::new(PushOptions::new().ca_certificate_pem(b"x".to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_push_client_format() {
+        let counter = Counter::new("test_counter", "test help").unwrap();
+        counter.inc();
+        let text = "# HELP test_counter test help\n# TYPE test_counter counter\ntest_counter 1\n";
+
+        let (addr, server) = serve(2, 200);
+        let client = PushClient::new(PushOptions::new().format(PushFormat::Text)).unwrap();
+        client
+            .push_collector(
+                "test",
+                HashMap::new(),
+                &addr,
+                vec![Box::new(counter.clone())],
+            )
+            .unwrap();
+        let client = PushClient::new(PushOptions::new().gzip(true)).unwrap();
+        client
+            .push_metrics("test", HashMap::new(), &addr, counter.collect())
+            .unwrap();
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].header("content-type"), Some(TEXT_FORMAT));
+        assert_eq!(requests[0].header("content-encoding"), None);
+        assert_eq!(requests[0].body, text.as_bytes());
+
+        assert_eq!(requests[1].header("content-encoding"), Some("gzip"));
+        let mut body = Vec::new();
+        libflate::gzip::Decoder::new(&requests[1].body[..])
+            .unwrap()
+            .read_to_end(&mut body)
+            .unwrap();
+        #[cfg(feature = "protobuf")]
+        {
+            use crate::encoder::PROTOBUF_FORMAT;
+            use protobuf::Message;
+
+            assert_eq!(requests[1].header("content-type"), Some(PROTOBUF_FORMAT));
+            let mf = proto::MetricFamily::parse_from_bytes(&body[1..]).unwrap();
+            assert_eq!(mf.get_name(), "test_counter");
+        }
+        #[cfg(not(feature = "protobuf"))]
+        assert_eq!(body, text.as_bytes());
+    }
+
+    #[test]
+    fn test_push_client_retries() {
+        let (addr, server) = serve_statuses(vec![503, 500, 202]);
+        let client =
+            PushClient::new(PushOptions::new().retries(2, Duration::from_millis(1))).unwrap();
+        client
+            .delete_metrics("test", HashMap::new(), &addr)
+            .unwrap();
+        assert_eq!(server.join().unwrap().len(), 3);
+
+        // Client errors are not retried.
+        let (addr, server) = serve_statuses(vec![400]);
+        assert!(client
+            .push_metrics("test", HashMap::new(), &addr, vec![])
+            .is_err());
+        assert_eq!(server.join().unwrap().len(), 1);
+
+        let (addr, server) = serve_statuses(vec![503, 503]);
+        let client =
+            PushClient::new(PushOptions::new().retries(1, Duration::from_millis(1))).unwrap();
+        let res = client.push_add_metrics("test", HashMap::new(), &addr, vec![]);
+        assert!(format!("{}", res.unwrap_err()).contains("503"));
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::counter::IntCounter;
+use crate::errors::{Error, Result};
+use crate::gauge::Gauge;
+use crate::metrics::Opts;
+use crate::push::{push_metrics, BasicAuthentication};
+use crate::push_client::PushClient;
+use crate::registry::{default_registry, Registry};
+
+const DEFAULT_PUSH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A builder of a background thread that periodically pushes the metrics of
+/// a [`Registry`] to a Pushgateway, for batch jobs that cannot be scraped.
+///
+/// Every push replaces the metrics previously pushed with the same job and
+/// grouping labels, like [`push_metrics`]. The thread pushes one last time
+/// when it is shut down, so the final values of the job are not lost.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use prometheus::{IntCounter, Pusher, Registry};
+///
+/// let registry = Registry::new();
+/// let processed = IntCounter::new("records_processed", "Number of records.").unwrap();
+/// registry.register(Box::new(processed.clone())).unwrap();
+///
+/// let pusher = Pusher::new("batch_job", "localhost:9091")
+///     .registry(registry)
+///     .interval(Duration::from_secs(10))
+///     .start()
+///     .unwrap();
+///
+/// processed.inc_by(100);
+///
+/// // Push the final values before exiting.
+/// pusher.shutdown().unwrap();
+/// ```
+pub struct Pusher {
+    job: String,
+    url: String,
+    grouping: HashMap<String, String>,
+    basic_auth: Option<BasicAuthentication>,
+    client: Option<PushClient>,
+    interval: Duration,
+    registry: Registry,
+    errors: IntCounter,
+    last_success: Gauge,
+}
+
+impl Pusher {
+    /// Create a [`Pusher`] for `job` that pushes the metrics of the default
+    /// registry to the Pushgateway at `url` every 15 seconds.
+    pub fn new<S1: Into<String>, S2: Into<String>>(job: S1, url: S2) -> Pusher {
+        // No job label: the Pushgateway adds it, and pushed metrics must not
+        // carry one.
+        let errors = IntCounter::with_opts(Opts::new(
+            "prometheus_push_errors_total",
+            "Number of failed pushes to the Pushgateway.",
+        ))
+        .unwrap();
+        let last_success = Gauge::with_opts(Opts::new(
+            "prometheus_push_last_success_timestamp_seconds",
+            "Unix time of the last successful push to the Pushgateway.",
+        ))
+        .unwrap();
+
+        Pusher {
+            job: job.into(),
+            url: url.into(),
+            grouping: HashMap::new(),
+            basic_auth: None,
+            client: None,
+            interval: DEFAULT_PUSH_INTERVAL,
+            registry: default_registry().clone(),
+            errors,
+            last_success,
+        }
+    }
+
+    /// Set the grouping labels, in addition to the job.
+    pub fn grouping(mut self, grouping: HashMap<String, String>) -> Self {
+        self.grouping = grouping;
+        self
+    }
+
+    /// Authenticate to the Pushgateway with Basic access authentication.
+    pub fn basic_auth(mut self, basic_auth: BasicAuthentication) -> Self {
+        self.basic_auth = Some(basic_auth);
+        self
+    }
+
+    /// Push with `client`, e.g. to send custom headers or retry failed
+    /// pushes. The client's options take precedence over
+    /// [`basic_auth`](Pusher::basic_auth).
+    pub fn client(mut self, client: PushClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Set the time between two pushes.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Push the metrics of `registry` instead of the default registry.
+    pub fn registry(mut self, registry: Registry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Return the counter of failed pushes. It is not registered anywhere,
+    /// register it to push or expose it.
+    pub fn errors(&self) -> &IntCounter {
+        &self.errors
+    }
+
+    /// Return the gauge holding the Unix time of the last successful push.
+    /// It is not registered anywhere, register it to push or expose it.
+    pub fn last_success(&self) -> &Gauge {
+        &self.last_success
+    }
+
+    /// Push the metrics once, on the calling thread.
+    pub fn push(&self) -> Result<()> {
+        let (grouping, mfs) = (self.grouping.clone(), self.registry.gather());
+        let res = match self.client {
+            Some(ref client) => client.push_metrics(&self.job, grouping, &self.url, mfs),
+            None => push_metrics(&self.job, grouping, &self.url, mfs, self.basic_auth.clone()),
+        };
+        match res {
+            Ok(()) => self.last_success.set_to_current_time(),
+            Err(_) => self.errors.inc(),
+        }
+        res
+    }
+
+    /// Start pushing on a background thread. The first push happens after
+    /// one interval.
+    pub fn start(self) -> io::Result<PusherHandle> {
+        let pusher = Arc::new(self);
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let p = pusher.clone();
+        let thread = thread::Builder::new()
+            .name("prometheus pusher".to_owned())
+            .spawn(move || loop {
+                match stopped.recv_timeout(p.interval) {
+                    // Failures are counted, the next push may succeed.
+                    Err(RecvTimeoutError::Timeout) => {
+                        let _ = p.push();
+                    }
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => return p.push(),
+                }
+            })?;
+
+        Ok(PusherHandle {
+            pusher,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl fmt::Debug for Pusher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pusher")
+            .field("job", &self.job)
+            .field("url", &self.url)
+            .field("grouping", &self.grouping)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+/// A handle to the background thread started by [`Pusher::start`].
+///
+/// Dropping the handle shuts the thread down, ignoring the result of the
+/// final push.
+#[derive(Debug)]
+pub struct PusherHandle {
+    pusher: Arc<Pusher>,
+    stop: Sender<()>,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl PusherHandle {
+    /// Return the [`Pusher`] run by the thread, e.g. to access its metrics.
+    pub fn pusher(&self) -> &Pusher {
+        &self.pusher
+    }
+
+    /// Stop the thread after a final push, and return the result of that
+    /// push.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop_and_join()
+    }
+
+    fn stop_and_join(&mut self) -> Result<()> {
+        let thread = match self.thread.take() {
+            Some(thread) => thread,
+            None => return Ok(()),
+        };
+        // The thread may be gone already if it panicked.
+        let _ = self.stop.send(());
+        thread
+            .join()
+            .unwrap_or_else(|_| Err(Error::Msg("pusher thread panicked".to_owned())))
+    }
+}
+
+impl Drop for PusherHandle {
+    fn drop(&mut self) {
+        let _ = self.stop_and_join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::Counter;
+    use crate::push_client::PushOptions;
+    use crate::test_http::serve;
+
+    #[test]
+    fn test_pusher_final_push() {
+        let (addr, server) = serve(1, 200);
+
+        let registry = Registry::new();
+        let counter = Counter::new("test_pusher_counter", "test help").unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+
+        let handle = Pusher::new("test", addr)
+            .registry(registry)
+            .interval(Duration::from_secs(3600))
+            .start()
+            .unwrap();
+        counter.inc_by(2.0);
+        handle.shutdown().unwrap();
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].line, "PUT /metrics/job/test HTTP/1.1");
+        assert!(!requests[0].body.is_empty());
+    }
+
+    #[test]
+    fn test_pusher_metrics() {
+        let (addr, server) = serve(3, 500);
+
+        let pusher = Pusher::new("test", addr)
+            .registry(Registry::new())
+            .interval(Duration::from_millis(10));
+        assert!(pusher.push().is_err());
+        assert_eq!(pusher.errors().get(), 1);
+        assert_eq!(pusher.last_success().get(), 0.0);
+
+        let handle = pusher.start().unwrap();
+        while handle.pusher().errors().get() < 2 {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(handle.shutdown().is_err());
+        assert_eq!(server.join().unwrap().len(), 3);
+
+        let (addr, server) = serve(1, 202);
+        let client = PushClient::new(PushOptions::new().bearer_token("secret")).unwrap();
+        let pusher = Pusher::new("test", addr)
+            .registry(Registry::new())
+            .client(client);
+        pusher.push().unwrap();
+        assert!(pusher.last_success().get() > 0.0);
+        assert_eq!(pusher.errors().get(), 0);
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].header("authorization"), Some("Bearer secret"));
+    }
+
+    #[test]
+    fn test_pusher_pushes_own_metrics() {
+        let (addr, server) = serve(2, 200);
+
+        let registry = Registry::new();
+        let pusher = Pusher::new("test", addr).registry(registry.clone());
+        registry
+            .register(Box::new(pusher.errors().clone()))
+            .unwrap();
+        registry
+            .register(Box::new(pusher.last_success().clone()))
+            .unwrap();
+        pusher.push().unwrap();
+        pusher.push().unwrap();
+        assert_eq!(pusher.errors().get(), 0);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].line, "PUT /metrics/job/test HTTP/1.1");
+        assert!(!requests[1].body.is_empty());
+    }
+}
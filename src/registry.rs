@@ -9,6 +9,7 @@ use std::sync::Arc;
 use parking_lot::RwLock;
 
 use crate::errors::{Error, Result};
+use crate::local_flush::flush_local_metrics;
 use crate::metrics::Collector;
 use crate::proto;
 
@@ -288,7 +289,15 @@ impl Registry {
     /// `gather` calls the Collect method of the registered Collectors and then
     /// gathers the collected metrics into a lexicographically sorted slice
     /// of MetricFamily protobufs.
+    ///
+    /// All live [`SharedLocalMetric`](crate::local::SharedLocalMetric)s are
+    /// flushed first, so that their buffered updates are included.
     pub fn gather(&self) -> Vec<proto::MetricFamily> {
+        {
+            #[cfg(test)]
+            let _isolated = crate::local_flush::GATHER_FLUSH.read();
+            flush_local_metrics();
+        }
         self.r.read().gather()
     }
 }
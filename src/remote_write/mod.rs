@@ -0,0 +1,763 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+/*!
+
+Export metrics with the Prometheus remote-write protocol.
+
+For processes that cannot be scraped, a [`RemoteWriter`] periodically gathers
+a [`Registry`] and sends the samples to a remote-write receiver, e.g.
+Prometheus with `--web.enable-remote-write-receiver`, Cortex or Thanos.
+
+Unlike the Pushgateway, the receiver stores every sample with its timestamp,
+so it is suited for long-lived processes.
+
+*/
+
+mod snappy;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::{HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::StatusCode;
+
+use crate::backoff::next_backoff;
+use crate::counter::IntCounter;
+use crate::errors::{Error, Result};
+use crate::gauge::Gauge;
+use crate::metrics::Opts;
+use crate::proto::{self, MetricType};
+use crate::registry::{default_registry, Registry};
+use crate::wire;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_QUEUE_CAPACITY: usize = 16;
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+const REMOTE_WRITE_VERSION: &str = "0.1.0";
+
+/// A label of a [`TimeSeries`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Label {
+    /// The label name, `__name__` for the metric name.
+    pub name: String,
+    /// The label value.
+    pub value: String,
+}
+
+/// A sample of a [`TimeSeries`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// The sample value.
+    pub value: f64,
+    /// The time of the sample, in milliseconds since the UNIX epoch.
+    pub timestamp_ms: i64,
+}
+
+/// A series of samples identified by its labels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeSeries {
+    /// The labels, sorted by name, including the metric name as `__name__`.
+    pub labels: Vec<Label>,
+    /// The samples, sorted by time.
+    pub samples: Vec<Sample>,
+}
+
+/// The message of the remote-write protocol.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WriteRequest {
+    /// The series to write.
+    pub timeseries: Vec<TimeSeries>,
+}
+
+impl WriteRequest {
+    /// Convert gathered metric families into series with a single sample.
+    /// Samples without an explicit timestamp get `timestamp_ms`.
+    ///
+    /// Like in the text format, a summary becomes a series per quantile, and
+    /// `_sum` and `_count` series. A histogram becomes a `_bucket` series per
+    /// bucket, including `+Inf`, and `_sum` and `_count` series.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use prometheus::remote_write::WriteRequest;
+    /// use prometheus::{IntCounter, Registry};
+    ///
+    /// let registry = Registry::new();
+    /// let counter = IntCounter::new("requests_total", "Number of requests.").unwrap();
+    /// registry.register(Box::new(counter.clone())).unwrap();
+    /// counter.inc();
+    ///
+    /// let request = WriteRequest::from_metric_families(&registry.gather(), 1_500_000_000_000);
+    /// let series = &request.timeseries[0];
+    /// assert_eq!(series.labels[0].name, "__name__");
+    /// assert_eq!(series.labels[0].value, "requests_total");
+    /// assert_eq!(series.samples[0].value, 1.0);
+    /// ```
+    pub fn from_metric_families(mfs: &[proto::MetricFamily], timestamp_ms: i64) -> WriteRequest {
+        let mut request = WriteRequest::default();
+        for mf in mfs {
+            let name = mf.get_name();
+            for m in mf.get_metric() {
+                let timestamp_ms = if m.get_timestamp_ms() != 0 {
+                    m.get_timestamp_ms()
+                } else {
+                    timestamp_ms
+                };
+                let mut add = |suffix: &str, extra: Option<(&str, String)>, value: f64| {
+                    request
+                        .timeseries
+                        .push(series(name, suffix, m, extra, value, timestamp_ms));
+                };
+
+                match mf.get_field_type() {
+                    MetricType::COUNTER => add("", None, m.get_counter().get_value()),
+                    MetricType::GAUGE => add("", None, m.get_gauge().get_value()),
+                    #[allow(deprecated)]
+                    MetricType::UNTYPED => add("", None, m.get_untyped().get_value()),
+                    MetricType::SUMMARY => {
+                        let s = m.get_summary();
+                        for q in s.get_quantile() {
+                            let quantile = q.get_quantile().to_string();
+                            add("", Some(("quantile", quantile)), q.get_value());
+                        }
+                        add("_sum", None, s.get_sample_sum());
+                        add("_count", None, s.get_sample_count() as f64);
+                    }
+                    MetricType::HISTOGRAM | MetricType::GAUGE_HISTOGRAM => {
+                        let h = m.get_histogram();
+                        let mut inf_seen = false;
+                        for b in h.get_bucket() {
+                            let upper_bound = b.get_upper_bound();
+                            if upper_bound.is_sign_positive() && upper_bound.is_infinite() {
+                                inf_seen = true;
+                            }
+                            let le = if inf_seen {
+                                "+Inf".to_owned()
+                            } else {
+                                upper_bound.to_string()
+                            };
+                            add("_bucket", Some(("le", le)), b.get_cumulative_count() as f64);
+                        }
+                        if !inf_seen {
+                            let count = h.get_sample_count() as f64;
+                            add("_bucket", Some(("le", "+Inf".to_owned())), count);
+                        }
+                        add("_sum", None, h.get_sample_sum());
+                        add("_count", None, h.get_sample_count() as f64);
+                    }
+                }
+            }
+        }
+        request
+    }
+
+    /// Encode the request as a protobuf `prometheus.WriteRequest` message,
+    /// without compression.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for ts in &self.timeseries {
+            wire::write_message(&mut buf, 1, |series| {
+                for label in &ts.labels {
+                    wire::write_message(series, 1, |l| {
+                        wire::write_bytes(l, 1, label.name.as_bytes());
+                        wire::write_bytes(l, 2, label.value.as_bytes());
+                    });
+                }
+                for sample in &ts.samples {
+                    wire::write_message(series, 2, |s| {
+                        wire::write_double(s, 1, sample.value);
+                        wire::write_uint(s, 2, sample.timestamp_ms as u64);
+                    });
+                }
+            });
+        }
+        buf
+    }
+}
+
+fn series(
+    name: &str,
+    suffix: &str,
+    m: &proto::Metric,
+    extra: Option<(&str, String)>,
+    value: f64,
+    timestamp_ms: i64,
+) -> TimeSeries {
+    let mut labels = Vec::with_capacity(m.get_label().len() + 2);
+    labels.push(Label {
+        name: "__name__".to_owned(),
+        value: format!("{}{}", name, suffix),
+    });
+    for lp in m.get_label() {
+        labels.push(Label {
+            name: lp.get_name().to_owned(),
+            value: lp.get_value().to_owned(),
+        });
+    }
+    if let Some((name, value)) = extra {
+        labels.push(Label {
+            name: name.to_owned(),
+            value,
+        });
+    }
+    labels.sort();
+
+    TimeSeries {
+        labels,
+        samples: vec![Sample {
+            value,
+            timestamp_ms,
+        }],
+    }
+}
+
+fn now_millis() -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_secs() as i64 * 1000 + i64::from(now.subsec_millis())
+}
+
+/// A builder of a background thread that periodically sends the metrics of a
+/// [`Registry`] to a remote-write receiver.
+///
+/// Requests failing with a 5xx or 429 status code, or without a response,
+/// are retried and kept in a bounded queue until they succeed, so short
+/// outages of the receiver lose no samples. When the queue is full, the
+/// oldest request is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use prometheus::remote_write::RemoteWriter;
+///
+/// let handle = RemoteWriter::new("http://localhost:9090/api/v1/write")
+///     .interval(Duration::from_secs(30))
+///     .bearer_token("secret")
+///     .start()
+///     .unwrap();
+///
+/// // Send the final values before exiting.
+/// handle.shutdown().unwrap();
+/// ```
+pub struct RemoteWriter {
+    url: String,
+    registry: Registry,
+    interval: Duration,
+    timeout: Duration,
+    headers: Vec<(String, String)>,
+    basic_auth: Option<(String, String)>,
+    bearer_token: Option<String>,
+    max_retries: u32,
+    retry_backoff: Duration,
+    queue_capacity: usize,
+    errors: IntCounter,
+    dropped: IntCounter,
+    last_success: Gauge,
+}
+
+impl RemoteWriter {
+    /// Create a [`RemoteWriter`] that sends the metrics of the default
+    /// registry to `url` every 15 seconds.
+    pub fn new<S: Into<String>>(url: S) -> RemoteWriter {
+        RemoteWriter {
+            url: url.into(),
+            registry: default_registry().clone(),
+            interval: DEFAULT_INTERVAL,
+            timeout: DEFAULT_TIMEOUT,
+            headers: Vec::new(),
+            basic_auth: None,
+            bearer_token: None,
+            max_retries: 3,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            errors: IntCounter::new(
+                "prometheus_remote_write_failed_requests_total",
+                "Number of failed remote-write requests, including retries.",
+            )
+            .unwrap(),
+            dropped: IntCounter::new(
+                "prometheus_remote_write_dropped_requests_total",
+                "Number of remote-write requests dropped without being sent.",
+            )
+            .unwrap(),
+            last_success: Gauge::with_opts(Opts::new(
+                "prometheus_remote_write_last_success_timestamp_seconds",
+                "Unix time of the last successful remote-write request.",
+            ))
+            .unwrap(),
+        }
+    }
+
+    /// Send the metrics of `registry` instead of the default registry.
+    pub fn registry(mut self, registry: Registry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Set the time between two gathers of the registry.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the timeout of each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send the header `name: value` with every request.
+    pub fn header<S1: Into<String>, S2: Into<String>>(mut self, name: S1, value: S2) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Authenticate with Basic access authentication.
+    pub fn basic_auth<S1: Into<String>, S2: Into<String>>(
+        mut self,
+        username: S1,
+        password: S2,
+    ) -> Self {
+        self.basic_auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Authenticate with the bearer token `token`.
+    pub fn bearer_token<S: Into<String>>(mut self, token: S) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// Retry a failing request up to `max_retries` times in a row, 3 by
+    /// default. The first retry waits `backoff`, which doubles with every
+    /// further retry, up to a minute. After that, the request stays queued until the next
+    /// interval.
+    pub fn retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Keep at most `capacity` requests queued while the receiver is
+    /// failing, 16 by default.
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self
+    }
+
+    /// Return the counter of failed requests. It is not registered anywhere,
+    /// register it to send or expose it.
+    pub fn errors(&self) -> &IntCounter {
+        &self.errors
+    }
+
+    /// Return the counter of requests dropped because the queue was full or
+    /// the receiver rejected them.
+    pub fn dropped(&self) -> &IntCounter {
+        &self.dropped
+    }
+
+    /// Return the gauge holding the Unix time of the last successful
+    /// request.
+    pub fn last_success(&self) -> &Gauge {
+        &self.last_success
+    }
+
+    /// Start sending on a background thread. The registry is first gathered
+    /// after one interval.
+    pub fn start(self) -> Result<RemoteWriteHandle> {
+        let mut builder = Client::builder().timeout(self.timeout);
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Error::Msg(format!("invalid header name {}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| Error::Msg(format!("invalid value of header {}: {}", name, e)))?;
+            headers.append(name, value);
+        }
+        builder = builder.default_headers(headers);
+        let client = builder.build().map_err(|e| Error::Msg(format!("{}", e)))?;
+
+        let writer = Arc::new(Worker {
+            config: self,
+            client,
+            queue: Mutex::new(VecDeque::new()),
+        });
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let w = writer.clone();
+        let thread = thread::Builder::new()
+            .name("prometheus remote write".to_owned())
+            .spawn(move || loop {
+                match stopped.recv_timeout(w.config.interval) {
+                    // Failed requests stay queued for the next interval.
+                    Err(RecvTimeoutError::Timeout) => {
+                        let _ = w.write();
+                    }
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => return w.write(),
+                }
+            })
+            .map_err(|e: io::Error| Error::Msg(format!("{}", e)))?;
+
+        Ok(RemoteWriteHandle {
+            writer,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl fmt::Debug for RemoteWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteWriter")
+            .field("url", &self.url)
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .field("max_retries", &self.max_retries)
+            .field("queue_capacity", &self.queue_capacity)
+            .finish()
+    }
+}
+
+struct Worker {
+    config: RemoteWriter,
+    client: Client,
+    // Compressed requests, oldest first.
+    queue: Mutex<VecDeque<Vec<u8>>>,
+}
+
+impl Worker {
+    // Gathers the registry and sends all queued requests.
+    fn write(&self) -> Result<()> {
+        let request =
+            WriteRequest::from_metric_families(&self.config.registry.gather(), now_millis());
+        let body = snappy::compress(&request.encode());
+
+        let mut queue = self.queue.lock();
+        if queue.len() >= self.config.queue_capacity {
+            queue.pop_front();
+            self.config.dropped.inc();
+        }
+        queue.push_back(body);
+
+        while let Some(body) = queue.front() {
+            match self.send(body) {
+                Ok(()) => {
+                    self.config.last_success.set_to_current_time();
+                }
+                Err(Retry::Later(e)) => return Err(e),
+                Err(Retry::Never(e)) => {
+                    // Sending it again would fail the same way.
+                    self.config.dropped.inc();
+                    queue.pop_front();
+                    return Err(e);
+                }
+            }
+            queue.pop_front();
+        }
+        Ok(())
+    }
+
+    fn send(&self, body: &[u8]) -> std::result::Result<(), Retry> {
+        let mut backoff = self.config.retry_backoff;
+        let mut retries = 0;
+        loop {
+            let res = self.request(body).send();
+            let err = match res {
+                Ok(ref response) if response.status().is_success() => return Ok(()),
+                Ok(ref response) => Error::Msg(format!(
+                    "unexpected status code {} while writing to {}",
+                    response.status(),
+                    self.config.url
+                )),
+                Err(ref e) => Error::Msg(format!("{}", e)),
+            };
+            self.config.errors.inc();
+
+            let retryable = match res {
+                Ok(ref response) => {
+                    response.status().is_server_error()
+                        || response.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(_) => true,
+            };
+            if !retryable {
+                return Err(Retry::Never(err));
+            }
+            if retries >= self.config.max_retries {
+                return Err(Retry::Later(err));
+            }
+
+            thread::sleep(backoff);
+            backoff = next_backoff(backoff);
+            retries += 1;
+        }
+    }
+
+    fn request(&self, body: &[u8]) -> RequestBuilder {
+        let mut builder = self
+            .client
+            .post(&self.config.url)
+            .header(CONTENT_TYPE, "application/x-protobuf")
+            .header(CONTENT_ENCODING, "snappy")
+            .header("X-Prometheus-Remote-Write-Version", REMOTE_WRITE_VERSION)
+            .body(body.to_vec());
+        if let Some((ref username, ref password)) = self.config.basic_auth {
+            builder = builder.basic_auth(username, Some(password));
+        }
+        if let Some(ref token) = self.config.bearer_token {
+            builder = builder.bearer_auth(token);
+        }
+        builder
+    }
+}
+
+enum Retry {
+    Later(Error),
+    Never(Error),
+}
+
+/// A handle to the background thread started by [`RemoteWriter::start`].
+///
+/// Dropping the handle shuts the thread down, ignoring the result of the
+/// final request.
+pub struct RemoteWriteHandle {
+    writer: Arc<Worker>,
+    stop: Sender<()>,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl RemoteWriteHandle {
+    /// Return the [`RemoteWriter`] run by the thread, e.g. to access its
+    /// metrics.
+    pub fn writer(&self) -> &RemoteWriter {
+        &self.writer.config
+    }
+
+    /// Stop the thread after gathering the registry one last time and
+    /// sending all queued requests. Returns an error if some could not be
+    /// sent.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop_and_join()
+    }
+
+    fn stop_and_join(&mut self) -> Result<()> {
+        let thread = match self.thread.take() {
+            Some(thread) => thread,
+            None => return Ok(()),
+        };
+        let _ = self.stop.send(());
+        thread
+            .join()
+            .unwrap_or_else(|_| Err(Error::Msg("remote write thread panicked".to_owned())))
+    }
+}
+
+impl Drop for RemoteWriteHandle {
+    fn drop(&mut self) {
+        let _ = self.stop_and_join();
+    }
+}
+
+impl fmt::Debug for RemoteWriteHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteWriteHandle")
+            .field("writer", &self.writer.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::CounterVec;
+    use crate::histogram::{Histogram, HistogramOpts};
+    use crate::test_http::serve_statuses;
+    use crate::wire::tests::read_fields;
+
+    fn decode(buf: &[u8]) -> WriteRequest {
+        let mut request = WriteRequest::default();
+        for (_, series) in read_fields(buf) {
+            let mut ts = TimeSeries {
+                labels: Vec::new(),
+                samples: Vec::new(),
+            };
+            for (field, value) in read_fields(series.bytes()) {
+                let fields = read_fields(value.bytes());
+                if field == 1 {
+                    ts.labels.push(Label {
+                        name: String::from_utf8(fields[0].1.bytes().to_vec()).unwrap(),
+                        value: String::from_utf8(fields[1].1.bytes().to_vec()).unwrap(),
+                    });
+                } else {
+                    ts.samples.push(Sample {
+                        value: fields[0].1.double(),
+                        timestamp_ms: fields[1].1.varint() as i64,
+                    });
+                }
+            }
+            request.timeseries.push(ts);
+        }
+        request
+    }
+
+    fn labels(ts: &TimeSeries) -> Vec<(&str, &str)> {
+        ts.labels
+            .iter()
+            .map(|l| (l.name.as_str(), l.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_write_request_conversion() {
+        let registry = Registry::new();
+        let counter = CounterVec::new(Opts::new("test_counter", "test help"), &["a"]).unwrap();
+        counter.with_label_values(&["x"]).inc_by(2.0);
+        counter.with_label_values(&["y"]).set_timestamp_ms(42);
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("test_histogram", "test help").buckets(vec![1.0, 2.0]),
+        )
+        .unwrap();
+        histogram.observe(1.5);
+        registry.register(Box::new(counter)).unwrap();
+        registry.register(Box::new(histogram)).unwrap();
+
+        let request = WriteRequest::from_metric_families(&registry.gather(), 1000);
+        let ts = &request.timeseries;
+        assert_eq!(ts.len(), 7);
+        assert_eq!(
+            labels(&ts[0]),
+            vec![("__name__", "test_counter"), ("a", "x")]
+        );
+        assert_eq!(
+            ts[0].samples,
+            vec![Sample {
+                value: 2.0,
+                timestamp_ms: 1000
+            }]
+        );
+        assert_eq!(ts[1].samples[0].timestamp_ms, 42);
+
+        let names: Vec<_> = ts[2..].iter().map(labels).collect();
+        assert_eq!(
+            names,
+            vec![
+                vec![("__name__", "test_histogram_bucket"), ("le", "1")],
+                vec![("__name__", "test_histogram_bucket"), ("le", "2")],
+                vec![("__name__", "test_histogram_bucket"), ("le", "+Inf")],
+                vec![("__name__", "test_histogram_sum")],
+                vec![("__name__", "test_histogram_count")],
+            ]
+        );
+        let values: Vec<_> = ts[2..].iter().map(|t| t.samples[0].value).collect();
+        assert_eq!(values, vec![0.0, 1.0, 1.0, 1.5, 1.0]);
+    }
+
+    #[test]
+    fn test_write_request_encode() {
+        let request = WriteRequest {
+            timeseries: vec![TimeSeries {
+                labels: vec![Label {
+                    name: "__name__".to_owned(),
+                    value: "up".to_owned(),
+                }],
+                samples: vec![Sample {
+                    value: 1.0,
+                    timestamp_ms: 300,
+                }],
+            }],
+        };
+
+        let mut expected = vec![0x0a, 30, 0x0a, 14, 0x0a, 8];
+        expected.extend_from_slice(b"__name__");
+        expected.extend_from_slice(&[0x12, 2, b'u', b'p']);
+        expected.extend_from_slice(&[0x12, 12, 0x09]);
+        expected.extend_from_slice(&1.0f64.to_le_bytes());
+        expected.extend_from_slice(&[0x10, 0xac, 0x02]);
+        assert_eq!(request.encode(), expected);
+        assert_eq!(decode(&expected), request);
+    }
+
+    #[test]
+    fn test_remote_writer() {
+        let (addr, server) = serve_statuses(vec![503, 204]);
+
+        let registry = Registry::new();
+        let counter = IntCounter::new("test_counter", "test help").unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+
+        let handle = RemoteWriter::new(format!("http://{}/api/v1/write", addr))
+            .registry(registry.clone())
+            .interval(Duration::from_secs(3600))
+            .retries(1, Duration::from_millis(1))
+            .bearer_token("secret")
+            .start()
+            .unwrap();
+        counter.inc();
+        handle.shutdown().unwrap();
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].line, "POST /api/v1/write HTTP/1.1");
+        assert_eq!(requests[1].header("content-encoding"), Some("snappy"));
+        assert_eq!(requests[1].header("authorization"), Some("Bearer secret"));
+        assert_eq!(
+            requests[1].header("x-prometheus-remote-write-version"),
+            Some(REMOTE_WRITE_VERSION)
+        );
+
+        let request = decode(&snappy::tests::decompress(&requests[1].body));
+        assert_eq!(request.timeseries.len(), 1);
+        let ts = &request.timeseries[0];
+        assert_eq!(labels(ts), vec![("__name__", "test_counter")]);
+        assert_eq!(ts.samples.len(), 1);
+        assert_eq!(ts.samples[0].value, 1.0);
+        assert!(ts.samples[0].timestamp_ms > 0);
+        assert!(ts.samples[0].timestamp_ms <= now_millis());
+    }
+
+    #[test]
+    fn test_remote_writer_queue() {
+        let (addr, server) = serve_statuses(vec![400, 204]);
+        let writer = RemoteWriter::new(format!("http://{}", addr))
+            .registry(Registry::new())
+            .retries(0, Duration::from_millis(1))
+            .queue_capacity(2);
+        let worker = Worker {
+            client: Client::new(),
+            config: writer,
+            queue: Mutex::new(VecDeque::new()),
+        };
+
+        // Rejected requests are dropped.
+        assert!(worker.write().is_err());
+        assert_eq!(worker.config.dropped().get(), 1);
+        assert_eq!(worker.config.errors().get(), 1);
+        assert!(worker.queue.lock().is_empty());
+
+        worker.write().unwrap();
+        assert!(worker.config.last_success().get() > 0.0);
+        drop(server.join().unwrap());
+
+        // The receiver is gone, the oldest requests are dropped when the
+        // queue is full.
+        for _ in 0..3 {
+            assert!(worker.write().is_err());
+        }
+        assert_eq!(worker.queue.lock().len(), 2);
+        assert_eq!(worker.config.dropped().get(), 2);
+    }
+}
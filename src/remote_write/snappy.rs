@@ -0,0 +1,191 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Compression in the snappy block format, which remote-write receivers
+//! expect. See https://github.com/google/snappy/blob/master/format_description.txt.
+//!
+//! Only the compressor is needed. It finds matches with a single hash table
+//! lookup per position, which is far from the best ratio but already shrinks
+//! the repetitive label sets of write requests considerably.
+
+use crate::wire::write_varint;
+
+// Matches are searched within blocks of this size, so that offsets fit in
+// two bytes.
+const MAX_BLOCK_SIZE: usize = 1 << 16;
+const MIN_MATCH: usize = 4;
+const HASH_BITS: u32 = 14;
+
+const TAG_LITERAL: u8 = 0b00;
+const TAG_COPY_2: u8 = 0b10;
+
+/// Compress `input` into the snappy block format.
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() / 2 + 16);
+    write_varint(&mut out, input.len() as u64);
+    let mut table = vec![usize::MAX; 1 << HASH_BITS];
+    for block in input.chunks(MAX_BLOCK_SIZE) {
+        compress_block(block, &mut table, &mut out);
+    }
+    out
+}
+
+fn compress_block(src: &[u8], table: &mut [usize], out: &mut Vec<u8>) {
+    for entry in table.iter_mut() {
+        *entry = usize::MAX;
+    }
+
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i + MIN_MATCH <= src.len() {
+        let word = load_u32(src, i);
+        let slot = &mut table[hash(word)];
+        let candidate = *slot;
+        *slot = i;
+
+        if candidate == usize::MAX || load_u32(src, candidate) != word {
+            i += 1;
+            continue;
+        }
+
+        let mut len = MIN_MATCH;
+        while i + len < src.len() && src[candidate + len] == src[i + len] {
+            len += 1;
+        }
+        emit_literal(&src[literal_start..i], out);
+        emit_copy(i - candidate, len, out);
+        i += len;
+        literal_start = i;
+    }
+    emit_literal(&src[literal_start..], out);
+}
+
+#[inline]
+fn load_u32(src: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([src[i], src[i + 1], src[i + 2], src[i + 3]])
+}
+
+#[inline]
+fn hash(word: u32) -> usize {
+    (word.wrapping_mul(0x1e35_a7bd) >> (32 - HASH_BITS)) as usize
+}
+
+fn emit_literal(literal: &[u8], out: &mut Vec<u8>) {
+    if literal.is_empty() {
+        return;
+    }
+    // Blocks are at most 64 KiB, so the length fits in two bytes.
+    let n = literal.len() - 1;
+    if n < 60 {
+        out.push((n as u8) << 2 | TAG_LITERAL);
+    } else if n < 1 << 8 {
+        out.push(60 << 2 | TAG_LITERAL);
+        out.push(n as u8);
+    } else {
+        out.push(61 << 2 | TAG_LITERAL);
+        out.extend_from_slice(&(n as u16).to_le_bytes());
+    }
+    out.extend_from_slice(literal);
+}
+
+fn emit_copy(offset: usize, mut len: usize, out: &mut Vec<u8>) {
+    // A copy holds at most 64 bytes. Keep the last one at least 4 bytes long
+    // to match what other encoders produce.
+    while len >= 68 {
+        emit_copy_2(offset, 64, out);
+        len -= 64;
+    }
+    if len > 64 {
+        emit_copy_2(offset, 60, out);
+        len -= 60;
+    }
+    emit_copy_2(offset, len, out);
+}
+
+fn emit_copy_2(offset: usize, len: usize, out: &mut Vec<u8>) {
+    out.push(((len - 1) as u8) << 2 | TAG_COPY_2);
+    out.extend_from_slice(&(offset as u16).to_le_bytes());
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Decompress the snappy block `input`, only supporting the tags the
+    /// compressor emits.
+    pub fn decompress(input: &[u8]) -> Vec<u8> {
+        let (mut len, mut shift, mut i) = (0usize, 0, 0);
+        loop {
+            let b = input[i];
+            i += 1;
+            len |= ((b & 0x7f) as usize) << shift;
+            shift += 7;
+            if b < 0x80 {
+                break;
+            }
+        }
+
+        let mut out = Vec::with_capacity(len);
+        while i < input.len() {
+            let tag = input[i];
+            i += 1;
+            match tag & 0b11 {
+                TAG_LITERAL => {
+                    let n = match tag >> 2 {
+                        60 => {
+                            i += 1;
+                            input[i - 1] as usize
+                        }
+                        61 => {
+                            i += 2;
+                            u16::from_le_bytes([input[i - 2], input[i - 1]]) as usize
+                        }
+                        n => n as usize,
+                    } + 1;
+                    out.extend_from_slice(&input[i..i + n]);
+                    i += n;
+                }
+                TAG_COPY_2 => {
+                    let n = (tag >> 2) as usize + 1;
+                    let offset = u16::from_le_bytes([input[i], input[i + 1]]) as usize;
+                    i += 2;
+                    for _ in 0..n {
+                        out.push(out[out.len() - offset]);
+                    }
+                }
+                _ => panic!("unsupported tag {}", tag),
+            }
+        }
+        assert_eq!(out.len(), len);
+        out
+    }
+
+    #[test]
+    fn test_compress() {
+        assert_eq!(compress(b""), vec![0]);
+        assert_eq!(compress(b"abc"), vec![3, 2 << 2, b'a', b'b', b'c']);
+        // A literal of 4 bytes, then a copy of 8 bytes at offset 4.
+        assert_eq!(
+            compress(b"abcdabcdabcd"),
+            vec![
+                12,
+                3 << 2,
+                b'a',
+                b'b',
+                b'c',
+                b'd',
+                7 << 2 | TAG_COPY_2,
+                4,
+                0
+            ]
+        );
+
+        let mut input = Vec::new();
+        for i in 0..20_000 {
+            input.extend_from_slice(format!("series_{}{{le=\"{}\"}} ", i % 97, i).as_bytes());
+        }
+        input.extend((0..70_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8));
+        let compressed = compress(&input);
+        assert!(compressed.len() < input.len());
+        assert_eq!(decompress(&compressed), input);
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::desc::Desc;
+use crate::errors::{Error, Result};
+use crate::gauge::{IntGauge, IntGaugeVec};
+use crate::metrics::{Collector, Metric, Opts};
+use crate::proto;
+
+#[derive(Debug)]
+struct StateSetCore {
+    desc: Arc<Desc>,
+    states: Arc<Vec<String>>,
+    // One gauge per state, in the order of `states`.
+    gauges: Vec<IntGauge>,
+    // Serializes writers, so that `set_only` never leaves two states enabled.
+    // Shared by all state sets of a vector, as several handles may exist for
+    // the same label values.
+    lock: Arc<Mutex<()>>,
+}
+
+/// A [`Collector`] that tracks a fixed set of boolean states, e.g. the
+/// enabled feature flags or the current phase of a state machine.
+///
+/// Every state is exported as a gauge sample that is either 1 (enabled) or 0
+/// (disabled), with the state name as the value of a label named after the
+/// metric itself.
+///
+/// # Examples
+///
+/// ```
+/// use prometheus::StateSet;
+///
+/// let state = StateSet::new("phase", "Current phase.", &["starting", "running"]).unwrap();
+/// state.set_only("running").unwrap();
+/// assert!(!state.get("starting").unwrap());
+/// assert!(state.get("running").unwrap());
+/// ```
+#[derive(Clone, Debug)]
+pub struct StateSet {
+    core: Arc<StateSetCore>,
+}
+
+impl StateSet {
+    /// Create a [`StateSet`] with the `name`, `help` and `states` arguments.
+    /// All states start disabled.
+    pub fn new<S1: Into<String>, S2: Into<String>>(
+        name: S1,
+        help: S2,
+        states: &[&str],
+    ) -> Result<StateSet> {
+        StateSet::with_opts(Opts::new(name, help), states)
+    }
+
+    /// Create a [`StateSet`] with the `opts` options and the given `states`.
+    /// All states start disabled.
+    pub fn with_opts(opts: Opts, states: &[&str]) -> Result<StateSet> {
+        StateSetVec::new(opts, &[], states)?.get_metric_with_label_values(&[])
+    }
+
+    /// Return the states of this [`StateSet`].
+    pub fn states(&self) -> &[String] {
+        &self.core.states
+    }
+
+    /// Enable or disable `state`.
+    pub fn set(&self, state: &str, value: bool) -> Result<()> {
+        let i = self.index(state)?;
+        let _guard = self.core.lock.lock();
+        self.core.gauges[i].set(value as i64);
+        Ok(())
+    }
+
+    /// Enable `state` and disable all others, for state sets that model an
+    /// enum.
+    pub fn set_only(&self, state: &str) -> Result<()> {
+        let i = self.index(state)?;
+        let _guard = self.core.lock.lock();
+        for (j, gauge) in self.core.gauges.iter().enumerate() {
+            gauge.set((i == j) as i64);
+        }
+        Ok(())
+    }
+
+    /// Return whether `state` is enabled.
+    pub fn get(&self, state: &str) -> Result<bool> {
+        let i = self.index(state)?;
+        Ok(self.core.gauges[i].get() != 0)
+    }
+
+    fn index(&self, state: &str) -> Result<usize> {
+        self.core
+            .states
+            .iter()
+            .position(|s| s == state)
+            .ok_or_else(|| Error::Msg(format!("unknown state {}", state)))
+    }
+}
+
+impl Collector for StateSet {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![&*self.core.desc]
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        let metrics: Vec<_> = self.core.gauges.iter().map(Metric::metric).collect();
+
+        let mut m = proto::MetricFamily::default();
+        m.set_name(self.core.desc.fq_name.clone());
+        m.set_help(self.core.desc.help.clone());
+        m.set_field_type(proto::MetricType::GAUGE);
+        m.set_metric(from_vec!(metrics));
+
+        vec![m]
+    }
+}
+
+/// A [`Collector`] that bundles a set of [`StateSet`]s that all share the
+/// same [`Desc`] and states, but have different values for their variable
+/// labels, e.g. the state of each connection in a pool.
+#[derive(Clone, Debug)]
+pub struct StateSetVec {
+    // Partitioned by the variable labels plus the state label.
+    vec: IntGaugeVec,
+    desc: Arc<Desc>,
+    states: Arc<Vec<String>>,
+    lock: Arc<Mutex<()>>,
+}
+
+impl StateSetVec {
+    /// Create a new [`StateSetVec`] based on the provided [`Opts`] and
+    /// `states`, partitioned by the given label names.
+    pub fn new(opts: Opts, label_names: &[&str], states: &[&str]) -> Result<StateSetVec> {
+        if states.is_empty() {
+            return Err(Error::Msg("state set needs at least one state".into()));
+        }
+        let mut seen = HashSet::with_capacity(states.len());
+        for state in states {
+            if state.is_empty() {
+                return Err(Error::Msg("empty state name".into()));
+            }
+            if !seen.insert(state) {
+                return Err(Error::Msg(format!("duplicate state {}", state)));
+            }
+        }
+
+        // The state label is named after the metric.
+        let state_label = opts.fq_name();
+        if opts.const_labels.contains_key(&state_label) || label_names.contains(&&*state_label) {
+            return Err(Error::Msg(format!(
+                "label name {} is reserved for the states",
+                state_label
+            )));
+        }
+
+        let mut variable_names = label_names.to_vec();
+        variable_names.push(&state_label);
+        let vec = IntGaugeVec::new(opts, &variable_names)?;
+
+        Ok(StateSetVec {
+            desc: Arc::new(vec.v.desc.clone()),
+            vec,
+            states: Arc::new(states.iter().map(|s| (*s).to_owned()).collect()),
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// `get_metric_with_label_values` returns the [`StateSet`] for the given
+    /// slice of label values (same order as the variable labels in Desc). If
+    /// that combination of label values is accessed for the first time, a new
+    /// [`StateSet`] with all states disabled is created.
+    ///
+    /// An error is returned if the number of label values is not the same as
+    /// the number of variable labels in Desc.
+    pub fn get_metric_with_label_values(&self, vals: &[&str]) -> Result<StateSet> {
+        let mut label_values = Vec::with_capacity(vals.len() + 1);
+        label_values.extend_from_slice(vals);
+        label_values.push("");
+
+        let mut gauges = Vec::with_capacity(self.states.len());
+        for state in self.states.iter() {
+            *label_values.last_mut().unwrap() = state;
+            gauges.push(self.vec.get_metric_with_label_values(&label_values)?);
+        }
+
+        Ok(StateSet {
+            core: Arc::new(StateSetCore {
+                desc: Arc::clone(&self.desc),
+                states: Arc::clone(&self.states),
+                gauges,
+                lock: Arc::clone(&self.lock),
+            }),
+        })
+    }
+
+    /// `with_label_values` works as `get_metric_with_label_values`, but panics if an error
+    /// occurs.
+    pub fn with_label_values(&self, vals: &[&str]) -> StateSet {
+        self.get_metric_with_label_values(vals).unwrap()
+    }
+
+    /// `remove_label_values` removes the [`StateSet`] for the given slice of
+    /// label values.
+    pub fn remove_label_values(&self, vals: &[&str]) -> Result<()> {
+        let mut label_values = Vec::with_capacity(vals.len() + 1);
+        label_values.extend_from_slice(vals);
+        label_values.push("");
+
+        for state in self.states.iter() {
+            *label_values.last_mut().unwrap() = state;
+            self.vec.remove_label_values(&label_values)?;
+        }
+
+        Ok(())
+    }
+
+    /// `reset` deletes all [`StateSet`]s in this vector.
+    pub fn reset(&self) {
+        self.vec.reset()
+    }
+}
+
+impl Collector for StateSetVec {
+    fn desc(&self) -> Vec<&Desc> {
+        self.vec.desc()
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        self.vec.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoder::{Encoder, TextEncoder};
+    use crate::registry::Registry;
+
+    #[test]
+    fn test_state_set() {
+        let state = StateSet::new("test_phase", "test help", &["a", "b", "c"]).unwrap();
+        assert_eq!(state.states(), ["a", "b", "c"]);
+        assert!(!state.get("a").unwrap());
+        assert!(state.get("d").is_err());
+        assert!(state.set("d", true).is_err());
+        assert!(state.set_only("d").is_err());
+
+        state.set("a", true).unwrap();
+        state.set("c", true).unwrap();
+        assert!(state.get("a").unwrap());
+        assert!(!state.get("b").unwrap());
+        assert!(state.get("c").unwrap());
+
+        state.set_only("b").unwrap();
+        assert!(!state.get("a").unwrap());
+        assert!(state.get("b").unwrap());
+        assert!(!state.get("c").unwrap());
+
+        let r = Registry::new();
+        r.register(Box::new(state.clone())).unwrap();
+        let mut writer = Vec::<u8>::new();
+        TextEncoder::new().encode(&r.gather(), &mut writer).unwrap();
+        let ans = r##"# HELP test_phase test help
+# TYPE test_phase gauge
+test_phase{test_phase="a"} 0
+test_phase{test_phase="b"} 1
+test_phase{test_phase="c"} 0
+"##;
+        assert_eq!(ans, String::from_utf8(writer).unwrap());
+    }
+
+    #[test]
+    fn test_state_set_invalid() {
+        assert!(StateSet::new("test_phase", "test help", &[]).is_err());
+        assert!(StateSet::new("test_phase", "test help", &[""]).is_err());
+        assert!(StateSet::new("test_phase", "test help", &["a", "a"]).is_err());
+        assert!(StateSet::new("test:phase", "test help", &["a"]).is_err());
+
+        let opts = Opts::new("test_phase", "test help").const_label("test_phase", "x");
+        assert!(StateSet::with_opts(opts, &["a"]).is_err());
+        let opts = Opts::new("test_phase", "test help");
+        assert!(StateSetVec::new(opts, &["test_phase"], &["a"]).is_err());
+    }
+
+    #[test]
+    fn test_state_set_vec() {
+        let opts = Opts::new("test_phase", "test help");
+        let vec = StateSetVec::new(opts, &["l1"], &["a", "b"]).unwrap();
+        assert!(vec.get_metric_with_label_values(&[]).is_err());
+
+        vec.with_label_values(&["v1"]).set_only("a").unwrap();
+        vec.with_label_values(&["v2"]).set_only("b").unwrap();
+        assert!(vec.with_label_values(&["v1"]).get("a").unwrap());
+        assert!(vec.with_label_values(&["v2"]).get("b").unwrap());
+
+        let mfs = vec.collect();
+        assert_eq!(mfs[0].get_metric().len(), 4);
+
+        assert!(vec.remove_label_values(&["v1"]).is_ok());
+        assert!(vec.remove_label_values(&["v1"]).is_err());
+        assert_eq!(vec.collect()[0].get_metric().len(), 2);
+
+        vec.reset();
+        assert!(vec.collect()[0].get_metric().is_empty());
+    }
+}
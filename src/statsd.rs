@@ -0,0 +1,690 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+/*!
+
+Bridge metrics to StatsD and DogStatsD.
+
+A [`StatsdExporter`] periodically gathers a [`Registry`] and sends it over
+UDP in the StatsD line format:
+
+* Counters are sent as counters (`|c`) of the increase since the previous
+  send, which is what StatsD aggregators expect. A counter that went down,
+  e.g. after being reset, is sent with its full value.
+* Gauges and untyped metrics are sent as gauges (`|g`).
+* Histograms are sent according to the [`HistogramMode`], and their sum and
+  count as counters of the increase.
+* Summaries are sent as a gauge per quantile, and their sum and count as
+  counters of the increase.
+
+With DogStatsD, labels are sent as tags. Otherwise they are appended to the
+metric name as dot-separated values, in the order of the label names.
+
+*/
+
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::ops::Range;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::counter::IntCounter;
+use crate::errors::{Error, Result};
+use crate::proto::{self, MetricType};
+use crate::registry::{default_registry, Registry};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+// Fits in the payload of an Ethernet frame with IPv4 or IPv6 and UDP.
+const DEFAULT_MAX_PACKET_SIZE: usize = 1432;
+
+/// How histograms are sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistogramMode {
+    /// Send the increase of each bucket as a counter named `<name>_bucket`,
+    /// with an `le` label holding the upper bound of the bucket. Buckets are
+    /// not cumulative.
+    Buckets,
+    /// Send the observations as a histogram (`|h`), or a distribution (`|d`)
+    /// with DogStatsD. The raw values are not kept by a
+    /// [`Histogram`](crate::Histogram), so the observations of a bucket are
+    /// sent as its upper bound, with a sample rate such that a single line
+    /// stands for all of them. Observations above the largest finite bound
+    /// are sent as that bound.
+    Observations,
+}
+
+/// A builder of a background thread that periodically sends the metrics of
+/// a [`Registry`] to a StatsD server.
+///
+/// # Examples
+///
+/// ```no_run
+/// use prometheus::statsd::StatsdExporter;
+///
+/// let handle = StatsdExporter::new("localhost:8125")
+///     .prefix("myapp")
+///     .dogstatsd(true)
+///     .start()
+///     .unwrap();
+///
+/// // Send the final values before exiting.
+/// handle.shutdown().unwrap();
+/// ```
+pub struct StatsdExporter {
+    addr: String,
+    registry: Registry,
+    interval: Duration,
+    prefix: Option<String>,
+    dogstatsd: bool,
+    histogram_mode: HistogramMode,
+    max_packet_size: usize,
+    errors: IntCounter,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    socket: Option<UdpSocket>,
+    // The value of each counter series at the previous send.
+    last: HashMap<String, f64>,
+}
+
+impl StatsdExporter {
+    /// Create a [`StatsdExporter`] that sends the metrics of the default
+    /// registry to the StatsD server at `addr`, e.g. `localhost:8125`,
+    /// every 10 seconds.
+    pub fn new<S: Into<String>>(addr: S) -> StatsdExporter {
+        StatsdExporter {
+            addr: addr.into(),
+            registry: default_registry().clone(),
+            interval: DEFAULT_INTERVAL,
+            prefix: None,
+            dogstatsd: false,
+            histogram_mode: HistogramMode::Buckets,
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+            errors: IntCounter::new(
+                "prometheus_statsd_failed_sends_total",
+                "Number of failed sends to StatsD.",
+            )
+            .unwrap(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Send the metrics of `registry` instead of the default registry.
+    pub fn registry(mut self, registry: Registry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Set the time between two sends.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Prepend `prefix` and a dot to every metric name.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Send labels as DogStatsD tags instead of appending them to the
+    /// metric name.
+    pub fn dogstatsd(mut self, dogstatsd: bool) -> Self {
+        self.dogstatsd = dogstatsd;
+        self
+    }
+
+    /// Set how histograms are sent, [`HistogramMode::Buckets`] by default.
+    pub fn histogram_mode(mut self, mode: HistogramMode) -> Self {
+        self.histogram_mode = mode;
+        self
+    }
+
+    /// Set the maximum size of a UDP packet, 1432 bytes by default. Lines
+    /// are batched into packets up to this size.
+    pub fn max_packet_size(mut self, size: usize) -> Self {
+        self.max_packet_size = size;
+        self
+    }
+
+    /// Return the counter of failed sends. It is not registered anywhere,
+    /// register it to send or expose it.
+    pub fn errors(&self) -> &IntCounter {
+        &self.errors
+    }
+
+    /// Gather the registry and send it once, on the calling thread.
+    pub fn send(&self) -> Result<()> {
+        let res = self.send_once();
+        if res.is_err() {
+            self.errors.inc();
+        }
+        res
+    }
+
+    fn send_once(&self) -> Result<()> {
+        let mut state = self.state.lock();
+        if state.socket.is_none() {
+            state.socket = Some(self.connect()?);
+        }
+
+        let state = &mut *state;
+        let (lines, mut current) = self.lines(&self.registry.gather(), &state.last);
+        let socket = state.socket.as_ref().unwrap();
+        for range in packets(&lines, self.max_packet_size) {
+            let start = range.start;
+            if let Err(e) = socket.send(packet(&lines[range]).as_bytes()) {
+                // The increases of this packet and the next ones are sent
+                // again next time, those of the previous packets are not.
+                for key in lines[start..].iter().filter_map(|l| l.counter.as_ref()) {
+                    match state.last.get(key) {
+                        Some(&previous) => current.insert(key.clone(), previous),
+                        None => current.remove(key),
+                    };
+                }
+                state.last = current;
+                return Err(e.into());
+            }
+        }
+        state.last = current;
+        Ok(())
+    }
+
+    fn connect(&self) -> Result<UdpSocket> {
+        let addr = self
+            .addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::Msg(format!("cannot resolve {}", self.addr)))?;
+        let local: SocketAddr = match addr {
+            SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+            SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(socket)
+    }
+
+    // Converts the metric families into lines, and returns them with the
+    // current values of counters, which replace `last` once sent.
+    fn lines(
+        &self,
+        mfs: &[proto::MetricFamily],
+        last: &HashMap<String, f64>,
+    ) -> (Vec<Line>, HashMap<String, f64>) {
+        let mut lines = Vec::new();
+        let mut current = HashMap::with_capacity(last.len());
+
+        for mf in mfs {
+            let name = match self.prefix {
+                Some(ref prefix) => format!("{}.{}", prefix, mf.get_name()),
+                None => mf.get_name().to_owned(),
+            };
+            for m in mf.get_metric() {
+                let labels: Vec<_> = m
+                    .get_label()
+                    .iter()
+                    .map(|lp| (lp.get_name(), lp.get_value()))
+                    .collect();
+                let mut w = LineWriter {
+                    exporter: self,
+                    lines: &mut lines,
+                    last,
+                    current: &mut current,
+                    labels: &labels,
+                };
+
+                match mf.get_field_type() {
+                    MetricType::COUNTER => w.counter(&name, None, m.get_counter().get_value()),
+                    MetricType::GAUGE => w.gauge(&name, None, m.get_gauge().get_value()),
+                    #[allow(deprecated)]
+                    MetricType::UNTYPED => w.gauge(&name, None, m.get_untyped().get_value()),
+                    MetricType::SUMMARY => {
+                        let s = m.get_summary();
+                        for q in s.get_quantile() {
+                            let quantile = q.get_quantile().to_string();
+                            w.gauge(&name, Some(("quantile", &quantile)), q.get_value());
+                        }
+                        w.counter(&format!("{}_sum", name), None, s.get_sample_sum());
+                        let count = s.get_sample_count() as f64;
+                        w.counter(&format!("{}_count", name), None, count);
+                    }
+                    MetricType::HISTOGRAM | MetricType::GAUGE_HISTOGRAM => {
+                        w.histogram(&name, m.get_histogram())
+                    }
+                }
+            }
+        }
+
+        // Series that are gone are not in `current`, so they are forgotten.
+        (lines, current)
+    }
+
+    /// Start sending on a background thread. The registry is first sent
+    /// after one interval.
+    pub fn start(self) -> io::Result<StatsdHandle> {
+        let exporter = Arc::new(self);
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let e = exporter.clone();
+        let thread = thread::Builder::new()
+            .name("prometheus statsd".to_owned())
+            .spawn(move || loop {
+                match stopped.recv_timeout(e.interval) {
+                    // Failures are counted, the next send carries the
+                    // increases.
+                    Err(RecvTimeoutError::Timeout) => {
+                        let _ = e.send();
+                    }
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => return e.send(),
+                }
+            })?;
+
+        Ok(StatsdHandle {
+            exporter,
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl fmt::Debug for StatsdExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatsdExporter")
+            .field("addr", &self.addr)
+            .field("interval", &self.interval)
+            .field("prefix", &self.prefix)
+            .field("dogstatsd", &self.dogstatsd)
+            .field("histogram_mode", &self.histogram_mode)
+            .finish()
+    }
+}
+
+// A line, with the key of the counter series whose increase it sends.
+struct Line {
+    text: String,
+    counter: Option<String>,
+}
+
+struct LineWriter<'a> {
+    exporter: &'a StatsdExporter,
+    lines: &'a mut Vec<Line>,
+    last: &'a HashMap<String, f64>,
+    current: &'a mut HashMap<String, f64>,
+    labels: &'a [(&'a str, &'a str)],
+}
+
+impl<'a> LineWriter<'a> {
+    fn line(
+        &mut self,
+        name: &str,
+        extra: Option<(&str, &str)>,
+        value: f64,
+        kind: &str,
+        rate: f64,
+        counter: Option<String>,
+    ) {
+        let mut line = sanitize(name);
+        if !self.exporter.dogstatsd {
+            for (_, v) in self.labels.iter().chain(extra.as_ref()) {
+                line.push('.');
+                line.push_str(&sanitize(v));
+            }
+        }
+        write!(line, ":{}|{}", value, kind).unwrap();
+        if rate < 1.0 {
+            write!(line, "|@{}", rate).unwrap();
+        }
+        if self.exporter.dogstatsd {
+            let mut sep = "|#";
+            for (n, v) in self.labels.iter().chain(extra.as_ref()) {
+                write!(line, "{}{}:{}", sep, sanitize_tag(n), sanitize_tag(v)).unwrap();
+                sep = ",";
+            }
+        }
+        self.lines.push(Line {
+            text: line,
+            counter,
+        });
+    }
+
+    // Returns the key of a cumulative value, and its increase since the
+    // previous send.
+    fn delta(&mut self, name: &str, extra: Option<(&str, &str)>, value: f64) -> (String, f64) {
+        let mut key = name.to_owned();
+        for (n, v) in self.labels.iter().chain(extra.as_ref()) {
+            write!(key, "\u{0}{}\u{0}{}", n, v).unwrap();
+        }
+        let previous = self.last.get(&key).cloned().unwrap_or(0.0);
+        self.current.insert(key.clone(), value);
+
+        if value >= previous {
+            (key, value - previous)
+        } else {
+            (key, value)
+        }
+    }
+
+    fn counter(&mut self, name: &str, extra: Option<(&str, &str)>, value: f64) {
+        let (key, delta) = self.delta(name, extra, value);
+        if delta != 0.0 {
+            self.line(name, extra, delta, "c", 1.0, Some(key));
+        }
+    }
+
+    fn gauge(&mut self, name: &str, extra: Option<(&str, &str)>, value: f64) {
+        // A value with a sign is a relative change in StatsD, so a negative
+        // gauge is first set to zero.
+        if value < 0.0 {
+            self.line(name, extra, 0.0, "g", 1.0, None);
+        }
+        self.line(name, extra, value, "g", 1.0, None);
+    }
+
+    fn histogram(&mut self, name: &str, h: &proto::Histogram) {
+        let bucket_name = format!("{}_bucket", name);
+        let mut buckets: Vec<_> = h
+            .get_bucket()
+            .iter()
+            .map(|b| (b.get_upper_bound(), b.get_cumulative_count()))
+            .filter(|&(upper_bound, _)| upper_bound.is_finite())
+            .collect();
+        buckets.push((f64::INFINITY, h.get_sample_count()));
+
+        let mut previous = 0;
+        let mut largest_bound = None;
+        for (upper_bound, cumulative_count) in buckets {
+            let count = cumulative_count.saturating_sub(previous) as f64;
+            previous = cumulative_count;
+            let le = if upper_bound.is_infinite() {
+                "+Inf".to_owned()
+            } else {
+                upper_bound.to_string()
+            };
+
+            match self.exporter.histogram_mode {
+                HistogramMode::Buckets => self.counter(&bucket_name, Some(("le", &le)), count),
+                HistogramMode::Observations => {
+                    let (key, delta) = self.delta(&bucket_name, Some(("le", &le)), count);
+                    if upper_bound.is_finite() {
+                        largest_bound = Some(upper_bound);
+                    }
+                    if let (true, Some(value)) = (delta > 0.0, largest_bound) {
+                        let kind = if self.exporter.dogstatsd { "d" } else { "h" };
+                        self.line(name, None, value, kind, 1.0 / delta, Some(key));
+                    }
+                }
+            }
+        }
+        self.counter(&format!("{}_sum", name), None, h.get_sample_sum());
+        self.counter(
+            &format!("{}_count", name),
+            None,
+            h.get_sample_count() as f64,
+        );
+    }
+}
+
+// Batches lines into packets of at most `max_size` bytes, unless a single
+// line is longer, and returns the range of lines of each packet.
+fn packets(lines: &[Line], max_size: usize) -> Vec<Range<usize>> {
+    let mut packets = Vec::new();
+    let (mut start, mut size) = (0, 0);
+    for (i, line) in lines.iter().enumerate() {
+        if i > start && size + 1 + line.text.len() > max_size {
+            packets.push(start..i);
+            start = i;
+        }
+        size = if i > start {
+            size + 1 + line.text.len()
+        } else {
+            line.text.len()
+        };
+    }
+    if start < lines.len() {
+        packets.push(start..lines.len());
+    }
+    packets
+}
+
+fn packet(lines: &[Line]) -> String {
+    let texts: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+    texts.join("\n")
+}
+
+// Replaces the characters that are special in the line format.
+fn sanitize(s: &str) -> String {
+    s.replace(
+        |c: char| c == ':' || c == '|' || c == '@' || c.is_whitespace(),
+        "_",
+    )
+}
+
+fn sanitize_tag(s: &str) -> String {
+    s.replace(
+        |c: char| c == ',' || c == '|' || c == '#' || c.is_whitespace(),
+        "_",
+    )
+}
+
+/// A handle to the background thread started by [`StatsdExporter::start`].
+///
+/// Dropping the handle shuts the thread down, ignoring the result of the
+/// final send.
+#[derive(Debug)]
+pub struct StatsdHandle {
+    exporter: Arc<StatsdExporter>,
+    stop: Sender<()>,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl StatsdHandle {
+    /// Return the [`StatsdExporter`] run by the thread, e.g. to access its
+    /// metrics.
+    pub fn exporter(&self) -> &StatsdExporter {
+        &self.exporter
+    }
+
+    /// Stop the thread after a final send, and return the result of that
+    /// send.
+    pub fn shutdown(mut self) -> Result<()> {
+        self.stop_and_join()
+    }
+
+    fn stop_and_join(&mut self) -> Result<()> {
+        let thread = match self.thread.take() {
+            Some(thread) => thread,
+            None => return Ok(()),
+        };
+        let _ = self.stop.send(());
+        thread
+            .join()
+            .unwrap_or_else(|_| Err(Error::Msg("statsd thread panicked".to_owned())))
+    }
+}
+
+impl Drop for StatsdHandle {
+    fn drop(&mut self) {
+        let _ = self.stop_and_join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::CounterVec;
+    use crate::gauge::Gauge;
+    use crate::histogram::{Histogram, HistogramOpts};
+    use crate::metrics::Opts;
+
+    fn texts(lines: Vec<Line>) -> Vec<String> {
+        lines.into_iter().map(|l| l.text).collect()
+    }
+
+    fn receive(socket: &UdpSocket) -> String {
+        let mut buf = [0; 2048];
+        let n = socket.recv(&mut buf).unwrap();
+        String::from_utf8(buf[..n].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_statsd_lines() {
+        let registry = Registry::new();
+        let counter = CounterVec::new(Opts::new("requests", "test help"), &["code"]).unwrap();
+        let gauge = Gauge::new("temperature", "test help").unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        registry.register(Box::new(gauge.clone())).unwrap();
+
+        let exporter = StatsdExporter::new("localhost:8125")
+            .registry(registry)
+            .prefix("app");
+        counter.with_label_values(&["200"]).inc_by(3.0);
+        gauge.set(-2.5);
+        let (lines, last) = exporter.lines(&exporter.registry.gather(), &HashMap::new());
+        assert_eq!(
+            texts(lines),
+            vec![
+                "app.requests.200:3|c",
+                "app.temperature:0|g",
+                "app.temperature:-2.5|g"
+            ]
+        );
+
+        // Only the increase is sent, unchanged counters are not.
+        counter.with_label_values(&["200"]).inc();
+        counter.with_label_values(&["500"]).inc();
+        gauge.set(1.0);
+        let (lines, last) = exporter.lines(&exporter.registry.gather(), &last);
+        assert_eq!(
+            texts(lines),
+            vec![
+                "app.requests.200:1|c",
+                "app.requests.500:1|c",
+                "app.temperature:1|g"
+            ]
+        );
+        let (lines, _) = exporter.lines(&exporter.registry.gather(), &last);
+        assert_eq!(texts(lines), vec!["app.temperature:1|g"]);
+    }
+
+    #[test]
+    fn test_statsd_histogram() {
+        let registry = Registry::new();
+        let histogram = Histogram::with_opts(
+            HistogramOpts::new("latency", "test help")
+                .const_label("region", "eu")
+                .buckets(vec![0.5, 1.0]),
+        )
+        .unwrap();
+        registry.register(Box::new(histogram.clone())).unwrap();
+        histogram.observe(0.1);
+        histogram.observe(0.2);
+        histogram.observe(5.0);
+
+        let exporter = StatsdExporter::new("localhost:8125")
+            .registry(registry.clone())
+            .dogstatsd(true);
+        let (lines, _) = exporter.lines(&registry.gather(), &HashMap::new());
+        assert_eq!(
+            texts(lines),
+            vec![
+                "latency_bucket:2|c|#region:eu,le:0.5",
+                "latency_bucket:1|c|#region:eu,le:+Inf",
+                "latency_sum:5.3|c|#region:eu",
+                "latency_count:3|c|#region:eu",
+            ]
+        );
+
+        let exporter = exporter.histogram_mode(HistogramMode::Observations);
+        let (lines, _) = exporter.lines(&registry.gather(), &HashMap::new());
+        assert_eq!(
+            texts(lines),
+            vec![
+                "latency:0.5|d|@0.5|#region:eu",
+                "latency:1|d|#region:eu",
+                "latency_sum:5.3|c|#region:eu",
+                "latency_count:3|c|#region:eu",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statsd_exporter() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+
+        let registry = Registry::new();
+        let counter = IntCounter::new("test_counter", "test help").unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        let handle = StatsdExporter::new(server.local_addr().unwrap().to_string())
+            .registry(registry)
+            .interval(Duration::from_secs(3600))
+            .max_packet_size(10)
+            .start()
+            .unwrap();
+
+        counter.inc_by(2);
+        assert!(handle.exporter().send().is_ok());
+        assert_eq!(receive(&server), "test_counter:2|c");
+        counter.inc();
+        handle.shutdown().unwrap();
+        assert_eq!(receive(&server), "test_counter:1|c");
+    }
+
+    #[test]
+    fn test_statsd_failed_send() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+
+        let registry = Registry::new();
+        let first = IntCounter::new("a_counter", "test help").unwrap();
+        let last = IntCounter::new("c_counter", "test help").unwrap();
+        registry.register(Box::new(first.clone())).unwrap();
+        registry.register(Box::new(last.clone())).unwrap();
+        let exporter = StatsdExporter::new(server.local_addr().unwrap().to_string())
+            .registry(registry.clone());
+
+        // Too large for a datagram, and sent between the counters.
+        let large = Gauge::new(format!("b{}", "_".repeat(70_000)), "test help").unwrap();
+        registry.register(Box::new(large.clone())).unwrap();
+        first.inc_by(2);
+        last.inc_by(2);
+        assert!(exporter.send().is_err());
+        assert_eq!(exporter.errors().get(), 1);
+        assert_eq!(receive(&server), "a_counter:2|c");
+
+        // The increase sent before the failure is not sent again, the one
+        // after it is not lost.
+        registry.unregister(Box::new(large)).unwrap();
+        first.inc();
+        last.inc();
+        exporter.send().unwrap();
+        assert_eq!(receive(&server), "a_counter:1|c\nc_counter:3|c");
+    }
+
+    #[test]
+    fn test_packets() {
+        let lines: Vec<_> = ["a:1|c", "b:1|c", "long:100|g"]
+            .iter()
+            .map(|text| Line {
+                text: text.to_string(),
+                counter: None,
+            })
+            .collect();
+        assert_eq!(packets(&lines, 11), vec![0..2, 2..3]);
+        assert_eq!(packet(&lines[0..2]), "a:1|c\nb:1|c");
+        assert_eq!(packets(&lines, 5), vec![0..1, 1..2, 2..3]);
+        assert!(packets(&[], 5).is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A minimal HTTP server for testing clients.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+#[derive(Debug)]
+pub(crate) struct Request {
+    pub line: String,
+    // Header names are lowercase.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+// Answers `n` requests with `status`, and returns them.
+pub(crate) fn serve(n: usize, status: u16) -> (String, JoinHandle<Vec<Request>>) {
+    serve_statuses(vec![status; n])
+}
+
+// Answers one request with each of `statuses` in turn.
+pub(crate) fn serve_statuses(statuses: Vec<u16>) -> (String, JoinHandle<Vec<Request>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for (status, stream) in statuses.into_iter().zip(listener.incoming()) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let mut headers = Vec::new();
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                let mut parts = header.splitn(2, ':');
+                let name = parts.next().unwrap().trim().to_lowercase();
+                let value = parts.next().unwrap_or_default().trim().to_owned();
+                headers.push((name, value));
+            }
+            let mut request = Request {
+                line: line.trim().to_owned(),
+                headers,
+                body: Vec::new(),
+            };
+            if let Some(len) = request.header("content-length") {
+                request.body = vec![0; len.parse().unwrap()];
+                reader.read_exact(&mut request.body).unwrap();
+            }
+            write!(
+                stream,
+                "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+            requests.push(request);
+        }
+        requests
+    });
+    (addr, handle)
+}
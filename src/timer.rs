@@ -1,12 +1,11 @@
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-/// Milliseconds since ANCHOR.
+use crate::clock;
+
+/// Milliseconds since the anchor of the installed clock.
 static RECENT: AtomicU64 = AtomicU64::new(0);
-lazy_static! {
-    static ref ANCHOR: Instant = Instant::now();
-}
 
 /// Convert a duration to millisecond.
 #[inline]
@@ -14,12 +13,11 @@ pub fn duration_to_millis(dur: Duration) -> u64 {
     dur.as_secs() * 1000 + dur.subsec_millis() as u64
 }
 
-/// Returns milliseconds since ANCHOR.
+/// Returns milliseconds since the anchor of the installed clock.
 ///
-/// ANCHOR is some fixed point in history.
+/// The anchor is some fixed point in history, see [`Clock::now`](crate::clock::Clock::now).
 pub fn now_millis() -> u64 {
-    let res = Instant::now();
-    let t = duration_to_millis(res.saturating_duration_since(*ANCHOR));
+    let t = duration_to_millis(clock::now());
     let mut recent = RECENT.load(Ordering::Relaxed);
     loop {
         if recent > t {
@@ -37,15 +35,30 @@ pub fn recent_millis() -> u64 {
     RECENT.load(Ordering::Relaxed)
 }
 
+// Forgets the time of the manual clock of a test, which may be ahead of the
+// system clock.
+#[cfg(test)]
+pub(crate) fn reset_recent() {
+    RECENT.store(duration_to_millis(clock::now()), Ordering::Relaxed);
+}
+
 lazy_static! {
     static ref UPDATER_IS_RUNNING: AtomicBool = AtomicBool::new(false);
 }
 
-const CHECK_UPDATE_INTERVAL: Duration = Duration::from_millis(200);
+pub(crate) const CHECK_UPDATE_INTERVAL: Duration = Duration::from_millis(200);
 
 /// Ensures background updater is running, which will call `now_millis` periodically.
 pub fn ensure_updater() {
-    if !UPDATER_IS_RUNNING.compare_and_swap(false, true, Ordering::SeqCst) {
+    // Called on every coarse clock read, so check before contending on the flag.
+    if UPDATER_IS_RUNNING.load(Ordering::Relaxed) {
+        return;
+    }
+    if UPDATER_IS_RUNNING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_ok()
+    {
+        now_millis();
         std::thread::Builder::new()
             .name("time updater".to_owned())
             .spawn(|| loop {
@@ -58,9 +71,10 @@ pub fn ensure_updater() {
 
 #[cfg(test)]
 mod tests {
-    use std::thread;
     use std::time::Duration;
 
+    use crate::clock;
+
     #[test]
     fn test_duration_to_millis() {
         let cases = vec![(1, 1, 1000), (0, 1_000_000, 1), (3, 103_000_000, 3103)];
@@ -72,11 +86,15 @@ mod tests {
 
     #[test]
     fn test_time_update() {
-        assert_eq!(super::recent_millis(), 0);
+        let (clock, _guard) = clock::manual_for_test();
         let now = super::now_millis();
         assert_eq!(super::recent_millis(), now);
+
+        // The updater reads the installed clock, which does not move.
         super::ensure_updater();
-        thread::sleep(super::CHECK_UPDATE_INTERVAL * 2);
-        assert!(super::recent_millis() > now);
+        assert_eq!(super::recent_millis(), now);
+
+        clock.advance(super::CHECK_UPDATE_INTERVAL * 2);
+        assert_eq!(super::recent_millis(), now + 400);
     }
 }
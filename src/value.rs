@@ -1,9 +1,10 @@
 // Copyright 2014 The Prometheus Authors
 // Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
 
-use crate::atomic64::{Atomic, Number};
+use crate::atomic64::{Atomic, AtomicI64, Number};
 use crate::desc::{Desc, Describer};
 use crate::errors::{Error, Result};
+use crate::metrics::Created;
 use crate::proto::{Counter, Gauge, LabelPair, Metric, MetricFamily, MetricType};
 
 /// `ValueType` is an enumeration of metric types that represent a simple value
@@ -34,6 +35,9 @@ pub struct Value<P: Atomic> {
     pub val: P,
     pub val_type: ValueType,
     pub label_pairs: Vec<LabelPair>,
+    created: Created,
+    // Milliseconds since the UNIX epoch, 0 if the value is current.
+    timestamp_ms: AtomicI64,
 }
 
 impl<P: Atomic> Value<P> {
@@ -51,6 +55,8 @@ impl<P: Atomic> Value<P> {
             val: P::new(val),
             val_type,
             label_pairs,
+            created: Created::now(),
+            timestamp_ms: AtomicI64::new(0),
         })
     }
 
@@ -64,6 +70,22 @@ impl<P: Atomic> Value<P> {
         self.val.set(val);
     }
 
+    /// Set the value back to 0 and restart the creation time.
+    pub fn reset(&self) {
+        self.val.set(P::T::from_i64(0));
+        self.created.reset();
+    }
+
+    #[inline]
+    pub fn set_timestamp_ms(&self, timestamp_ms: i64) {
+        self.timestamp_ms.set(timestamp_ms);
+    }
+
+    #[inline]
+    pub fn get_timestamp_ms(&self) -> i64 {
+        self.timestamp_ms.get()
+    }
+
     #[inline]
     pub fn inc_by(&self, val: P::T) {
         self.val.inc_by(val);
@@ -84,15 +106,34 @@ impl<P: Atomic> Value<P> {
         self.val.dec_by(val)
     }
 
+    #[inline]
+    pub fn set_max(&self, val: P::T) {
+        self.val.fetch_max(val);
+    }
+
+    #[inline]
+    pub fn set_min(&self, val: P::T) {
+        self.val.fetch_min(val);
+    }
+
     pub fn metric(&self) -> Metric {
         let mut m = Metric::default();
         m.set_label(from_vec!(self.label_pairs.clone()));
 
+        let timestamp_ms = self.get_timestamp_ms();
+        if timestamp_ms != 0 {
+            m.set_timestamp_ms(timestamp_ms);
+        }
+
         let val = self.get();
         match self.val_type {
             ValueType::Counter => {
                 let mut counter = Counter::default();
                 counter.set_value(val.into_f64());
+                let (secs, nanos) = self.created.to_parts();
+                let created = counter.mut_created_timestamp();
+                created.set_seconds(secs);
+                created.set_nanos(nanos);
                 m.set_counter(counter);
             }
             ValueType::Gauge => {
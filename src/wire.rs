@@ -0,0 +1,155 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Writers of the protobuf wire format, for messages that are not part of
+//! the metric model, so that exporters need neither generated code nor the
+//! `protobuf` feature.
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_FIXED64: u32 = 1;
+const WIRE_LEN: u32 = 2;
+
+pub fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn write_key(buf: &mut Vec<u8>, field: u32, wire_type: u32) {
+    write_varint(buf, u64::from(field << 3 | wire_type));
+}
+
+/// Writes a varint field, e.g. an `int64`, `uint32`, `bool` or enum.
+pub fn write_uint(buf: &mut Vec<u8>, field: u32, v: u64) {
+    write_key(buf, field, WIRE_VARINT);
+    write_varint(buf, v);
+}
+
+/// Writes a `fixed64` or `sfixed64` field.
+pub fn write_fixed64(buf: &mut Vec<u8>, field: u32, v: u64) {
+    write_key(buf, field, WIRE_FIXED64);
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+/// Writes a `double` field.
+pub fn write_double(buf: &mut Vec<u8>, field: u32, v: f64) {
+    write_fixed64(buf, field, v.to_bits());
+}
+
+/// Writes a length-delimited field: a string, bytes, an embedded message or
+/// a packed repeated field.
+pub fn write_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    write_key(buf, field, WIRE_LEN);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+/// Writes an embedded message, encoded by `f`.
+pub fn write_message<F: FnOnce(&mut Vec<u8>)>(buf: &mut Vec<u8>, field: u32, f: F) {
+    let mut msg = Vec::new();
+    f(&mut msg);
+    write_bytes(buf, field, &msg);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The value of a field read by [`read_fields`].
+    #[derive(Debug, PartialEq)]
+    pub enum Value<'a> {
+        Varint(u64),
+        Fixed64(u64),
+        Bytes(&'a [u8]),
+    }
+
+    impl<'a> Value<'a> {
+        pub fn varint(&self) -> u64 {
+            match *self {
+                Value::Varint(v) => v,
+                ref v => panic!("not a varint: {:?}", v),
+            }
+        }
+
+        pub fn double(&self) -> f64 {
+            match *self {
+                Value::Fixed64(v) => f64::from_bits(v),
+                ref v => panic!("not a fixed64: {:?}", v),
+            }
+        }
+
+        pub fn bytes(&self) -> &'a [u8] {
+            match *self {
+                Value::Bytes(b) => b,
+                ref v => panic!("not length-delimited: {:?}", v),
+            }
+        }
+    }
+
+    fn read_varint(buf: &mut &[u8]) -> u64 {
+        let (mut v, mut shift) = (0, 0);
+        loop {
+            let b = buf[0];
+            *buf = &buf[1..];
+            v |= u64::from(b & 0x7f) << shift;
+            if b < 0x80 {
+                return v;
+            }
+            shift += 7;
+        }
+    }
+
+    /// Read the fields of a message, in order, only supporting the wire
+    /// types the writers emit.
+    pub fn read_fields(mut buf: &[u8]) -> Vec<(u32, Value<'_>)> {
+        let mut fields = Vec::new();
+        while !buf.is_empty() {
+            let key = read_varint(&mut buf);
+            let value = match key as u32 & 7 {
+                WIRE_VARINT => Value::Varint(read_varint(&mut buf)),
+                WIRE_FIXED64 => {
+                    let (v, rest) = buf.split_at(8);
+                    buf = rest;
+                    let mut bytes = [0; 8];
+                    bytes.copy_from_slice(v);
+                    Value::Fixed64(u64::from_le_bytes(bytes))
+                }
+                WIRE_LEN => {
+                    let len = read_varint(&mut buf) as usize;
+                    let (v, rest) = buf.split_at(len);
+                    buf = rest;
+                    Value::Bytes(v)
+                }
+                t => panic!("unsupported wire type {}", t),
+            };
+            fields.push(((key >> 3) as u32, value));
+        }
+        fields
+    }
+
+    #[test]
+    fn test_write_fields() {
+        let mut buf = Vec::new();
+        write_uint(&mut buf, 2, 300);
+        write_double(&mut buf, 1, 1.0);
+        write_message(&mut buf, 16, |m| write_bytes(m, 1, b"ab"));
+        assert_eq!(
+            buf,
+            vec![
+                0x10, 0xac, 0x02, 0x09, 0, 0, 0, 0, 0, 0, 0xf0, 0x3f, 0x82, 0x01, 4, 0x0a, 2, b'a',
+                b'b'
+            ]
+        );
+
+        let fields = read_fields(&buf);
+        assert_eq!(fields[0].0, 2);
+        assert_eq!(fields[0].1.varint(), 300);
+        assert_eq!(fields[1].1.double(), 1.0);
+        assert_eq!(fields[2].0, 16);
+        assert_eq!(
+            read_fields(fields[2].1.bytes()),
+            vec![(1, Value::Bytes(b"ab"))]
+        );
+    }
+}
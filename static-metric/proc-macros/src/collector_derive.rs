@@ -0,0 +1,299 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use proc_macro2::TokenStream as Tokens;
+use syn::spanned::Spanned;
+use syn::*;
+
+/// Metric types that `#[derive(Collector)]` knows how to construct. Fields of
+/// any other type are treated as nested collectors.
+const METRIC_TYPES: &[&str] = &[
+    "Counter",
+    "IntCounter",
+    "UIntCounter",
+    "Gauge",
+    "IntGauge",
+    "UIntGauge",
+    "Histogram",
+    "GaugeHistogram",
+];
+
+/// Options of `#[collector(...)]` on the struct.
+#[derive(Default)]
+struct CollectorOptions {
+    namespace: Option<LitStr>,
+    subsystem: Option<LitStr>,
+}
+
+/// Options of `#[metric(...)]` on a field.
+#[derive(Default)]
+struct MetricOptions {
+    name: Option<LitStr>,
+    help: Option<LitStr>,
+    labels: Vec<LitStr>,
+    buckets: Option<Vec<Lit>>,
+    skip: bool,
+}
+
+enum FieldKind {
+    Metric { vec: bool, histogram: bool },
+    Nested,
+}
+
+struct CollectorField {
+    ident: Ident,
+    ty: Type,
+    kind: FieldKind,
+    options: MetricOptions,
+    doc: Option<String>,
+}
+
+/// Expands `#[derive(Collector)]`.
+pub fn derive_collector(input: DeriveInput) -> Result<Tokens> {
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(ref fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "`Collector` can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    let options = parse_collector_options(&input.attrs)?;
+    let fields = fields.iter().map(parse_field).collect::<Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let collected: Vec<_> = fields
+        .iter()
+        .filter(|f| !f.options.skip)
+        .map(|f| &f.ident)
+        .collect();
+    let constructors = fields
+        .iter()
+        .map(|f| constructor(f, &options))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl #impl_generics ::prometheus::core::Collector for #name #ty_generics #where_clause {
+            fn desc(&self) -> ::std::vec::Vec<&::prometheus::core::Desc> {
+                let mut descs = ::std::vec::Vec::new();
+                #(
+                    descs.extend(::prometheus::core::Collector::desc(&self.#collected));
+                )*
+                descs
+            }
+
+            fn collect(&self) -> ::std::vec::Vec<::prometheus::proto::MetricFamily> {
+                let mut mfs = ::std::vec::Vec::new();
+                #(
+                    mfs.extend(::prometheus::core::Collector::collect(&self.#collected));
+                )*
+                mfs
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Create all metrics of this collector.
+            pub fn new() -> ::prometheus::Result<Self> {
+                Ok(#name {
+                    #(#constructors,)*
+                })
+            }
+        }
+    })
+}
+
+fn constructor(field: &CollectorField, options: &CollectorOptions) -> Result<Tokens> {
+    let ident = &field.ident;
+    let ty = &field.ty;
+
+    if field.options.skip {
+        return Ok(quote! { #ident: ::std::default::Default::default() });
+    }
+
+    let (vec, histogram) = match field.kind {
+        FieldKind::Metric { vec, histogram } => (vec, histogram),
+        FieldKind::Nested => return Ok(quote! { #ident: <#ty>::new()? }),
+    };
+
+    let name = match field.options.name {
+        Some(ref name) => name.clone(),
+        None => LitStr::new(&ident.to_string(), ident.span()),
+    };
+    let help = match (&field.options.help, &field.doc) {
+        (Some(help), _) => help.clone(),
+        (None, Some(doc)) => LitStr::new(doc, ident.span()),
+        (None, None) => {
+            return Err(Error::new(
+                ident.span(),
+                "missing help text, add a doc comment or `#[metric(help = \"...\")]`",
+            ))
+        }
+    };
+
+    let mut opts = if histogram {
+        quote! { ::prometheus::HistogramOpts::new(#name, #help) }
+    } else {
+        quote! { ::prometheus::Opts::new(#name, #help) }
+    };
+    if let Some(ref namespace) = options.namespace {
+        opts = quote! { #opts.namespace(#namespace) };
+    }
+    if let Some(ref subsystem) = options.subsystem {
+        opts = quote! { #opts.subsystem(#subsystem) };
+    }
+    if let Some(ref buckets) = field.options.buckets {
+        if !histogram {
+            return Err(Error::new(
+                ident.span(),
+                "`buckets` is only supported for histograms",
+            ));
+        }
+        let buckets = buckets.iter().map(|b| quote! { #b as f64 });
+        opts = quote! { #opts.buckets(vec![#(#buckets),*]) };
+    }
+
+    let labels = &field.options.labels;
+    if vec {
+        Ok(quote! { #ident: <#ty>::new(#opts, &[#(#labels),*])? })
+    } else if !labels.is_empty() {
+        Err(Error::new(
+            ident.span(),
+            "`labels` is only supported for metric vectors",
+        ))
+    } else {
+        Ok(quote! { #ident: <#ty>::with_opts(#opts)? })
+    }
+}
+
+fn parse_field(field: &Field) -> Result<CollectorField> {
+    let ident = field.ident.clone().unwrap();
+
+    let mut options = MetricOptions::default();
+    for attr in &field.attrs {
+        if !attr.path.is_ident("metric") {
+            continue;
+        }
+        for nested in parse_list(attr)? {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("name") => {
+                    options.name = Some(lit_str(&nv.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("help") => {
+                    options.help = Some(lit_str(&nv.lit)?);
+                }
+                NestedMeta::Meta(Meta::List(ref list)) if list.path.is_ident("labels") => {
+                    for label in &list.nested {
+                        match label {
+                            NestedMeta::Lit(lit) => options.labels.push(lit_str(lit)?),
+                            _ => return Err(Error::new(label.span(), "expected a label name")),
+                        }
+                    }
+                }
+                NestedMeta::Meta(Meta::List(ref list)) if list.path.is_ident("buckets") => {
+                    let mut buckets = Vec::new();
+                    for bucket in &list.nested {
+                        match bucket {
+                            NestedMeta::Lit(lit @ Lit::Float(_))
+                            | NestedMeta::Lit(lit @ Lit::Int(_)) => buckets.push(lit.clone()),
+                            _ => return Err(Error::new(bucket.span(), "expected a number")),
+                        }
+                    }
+                    options.buckets = Some(buckets);
+                }
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("skip") => {
+                    options.skip = true;
+                }
+                _ => return Err(Error::new(nested.span(), "unknown metric option")),
+            }
+        }
+    }
+
+    let type_name = match field.ty {
+        Type::Path(ref path) => path
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+    let base_name = type_name.trim_end_matches("Vec");
+    let kind = if METRIC_TYPES.contains(&base_name) {
+        FieldKind::Metric {
+            vec: base_name.len() != type_name.len(),
+            histogram: base_name.ends_with("Histogram"),
+        }
+    } else {
+        FieldKind::Nested
+    };
+
+    Ok(CollectorField {
+        ident,
+        ty: field.ty.clone(),
+        kind,
+        options,
+        doc: parse_doc(&field.attrs),
+    })
+}
+
+fn parse_collector_options(attrs: &[Attribute]) -> Result<CollectorOptions> {
+    let mut options = CollectorOptions::default();
+    for attr in attrs {
+        if !attr.path.is_ident("collector") {
+            continue;
+        }
+        for nested in parse_list(attr)? {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("namespace") => {
+                    options.namespace = Some(lit_str(&nv.lit)?);
+                }
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("subsystem") => {
+                    options.subsystem = Some(lit_str(&nv.lit)?);
+                }
+                _ => return Err(Error::new(nested.span(), "unknown collector option")),
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Joins the lines of the doc comments, which become the help text.
+fn parse_doc(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<_> = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(s), ..
+            })) => Some(s.value().trim().to_owned()),
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(" "))
+    }
+}
+
+fn parse_list(attr: &Attribute) -> Result<Vec<NestedMeta>> {
+    match attr.parse_meta()? {
+        Meta::List(list) => Ok(list.nested.into_iter().collect()),
+        meta => Err(Error::new(meta.span(), "expected a list of options")),
+    }
+}
+
+fn lit_str(lit: &Lit) -> Result<LitStr> {
+    match lit {
+        Lit::Str(s) => Ok(s.clone()),
+        _ => Err(Error::new(lit.span(), "expected a string literal")),
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use proc_macro2::TokenStream as Tokens;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::*;
+
+/// Arguments of `#[instrument(histogram = "...", counter = "...", errors = "...")]`.
+/// Every argument is an expression, given as a string.
+#[derive(Default)]
+pub struct InstrumentArgs {
+    histogram: Option<Expr>,
+    counter: Option<Expr>,
+    errors: Option<Expr>,
+}
+
+impl InstrumentArgs {
+    pub fn parse(args: Punctuated<NestedMeta, Token![,]>) -> Result<InstrumentArgs> {
+        let mut parsed = InstrumentArgs::default();
+        for arg in args {
+            let nv = match arg {
+                NestedMeta::Meta(Meta::NameValue(ref nv)) => nv,
+                _ => return Err(Error::new(arg.span(), "expected `name = \"expression\"`")),
+            };
+            let expr = match nv.lit {
+                Lit::Str(ref s) => s.parse::<Expr>()?,
+                _ => return Err(Error::new(nv.lit.span(), "expected a string literal")),
+            };
+            let slot = if nv.path.is_ident("histogram") {
+                &mut parsed.histogram
+            } else if nv.path.is_ident("counter") {
+                &mut parsed.counter
+            } else if nv.path.is_ident("errors") {
+                &mut parsed.errors
+            } else {
+                return Err(Error::new(nv.path.span(), "unknown instrument option"));
+            };
+            if slot.replace(expr).is_some() {
+                return Err(Error::new(nv.path.span(), "duplicate instrument option"));
+            }
+        }
+
+        if parsed.histogram.is_none() && parsed.counter.is_none() && parsed.errors.is_none() {
+            return Err(Error::new(
+                proc_macro2::Span::call_site(),
+                "expected at least one of `histogram`, `counter` or `errors`",
+            ));
+        }
+        Ok(parsed)
+    }
+}
+
+/// Wraps the body of `item` to time and count its calls.
+pub fn instrument(args: InstrumentArgs, item: ItemFn) -> Result<Tokens> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+    let function = sig.ident.to_string();
+
+    // The timer observes the duration when dropped, also on early returns
+    // and panics.
+    let timer = args.histogram.map(|histogram| {
+        quote! {
+            let _prometheus_timer = {
+                use ::prometheus::instrument_support::InstrumentHistogram as _;
+                (#histogram).instrumented_histogram(#function).start_timer()
+            };
+        }
+    });
+    let counter = args.counter.map(|counter| {
+        quote! {
+            {
+                use ::prometheus::instrument_support::InstrumentCounter as _;
+                (#counter).inc_instrumented(#function);
+            }
+        }
+    });
+    let errors = args.errors.map(|errors| {
+        quote! {
+            if _prometheus_result.is_err() {
+                use ::prometheus::instrument_support::InstrumentCounter as _;
+                (#errors).inc_instrumented(#function);
+            }
+        }
+    });
+
+    // The body runs in a closure or an async block, so that `return` and `?`
+    // do not skip counting errors.
+    let output = match sig.output {
+        ReturnType::Type(_, ref ty) if !contains_impl_trait(ty) => Some(quote! { #ty }),
+        ReturnType::Default => Some(quote! { () }),
+        _ => None,
+    };
+    let result = if sig.asyncness.is_some() {
+        let annotation = output.map(|ty| quote! { : #ty });
+        quote! { let _prometheus_result #annotation = async move #block.await; }
+    } else {
+        let annotation = output.map(|ty| quote! { -> #ty });
+        quote! {
+            let _prometheus_result =
+                ::prometheus::instrument_support::call_once(move || #annotation #block);
+        }
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            #timer
+            #counter
+            #result
+            #errors
+            _prometheus_result
+        }
+    })
+}
+
+fn contains_impl_trait(ty: &Type) -> bool {
+    quote!(#ty).into_iter().any(|t| t.to_string() == "impl")
+}
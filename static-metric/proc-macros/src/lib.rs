@@ -11,6 +11,8 @@ extern crate syn;
 mod auto_flush_builder;
 mod auto_flush_from;
 mod builder;
+mod collector_derive;
+mod instrument;
 mod parser;
 mod register_macro;
 mod util;
@@ -24,6 +26,47 @@ use crate::auto_flush_from::AutoFlushFromDef;
 use auto_flush_builder::AutoFlushTokensBuilder;
 use proc_macro_hack::proc_macro_hack;
 
+/// Derive [`Collector`] for a struct whose fields are metrics, metric vectors
+/// or other collectors, along with a `new()` constructor creating all of them.
+///
+/// Metric names default to the field names, and help texts are taken from the
+/// doc comments. Fields can be customized with `#[metric(name = "...",
+/// help = "...", labels("...", ...), buckets(...))]` or excluded with
+/// `#[metric(skip)]`, in which case they are created with `Default`. The
+/// struct accepts `#[collector(namespace = "...", subsystem = "...")]`.
+///
+/// Fields of other types are treated as nested collectors, created with their
+/// own `new()`.
+///
+/// [`Collector`]: https://docs.rs/prometheus/*/prometheus/core/trait.Collector.html
+#[proc_macro_derive(Collector, attributes(collector, metric))]
+pub fn derive_collector(input: TokenStream) -> TokenStream {
+    let input: syn::DeriveInput = syn::parse(input).unwrap();
+    match collector_derive::derive_collector(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Time and count the calls of a function, sync or async.
+///
+/// `histogram` observes the duration of every call, `counter` counts the
+/// calls and `errors` counts the calls returning an `Err`. Each argument is an
+/// expression given as a string, evaluating to a `Histogram` or counter, or to
+/// a vector with a single label that is set to the function name.
+///
+/// Re-exported as `prometheus::instrument` with the `macros` feature.
+#[proc_macro_attribute]
+pub fn instrument(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+    let item = syn::parse_macro_input!(item as syn::ItemFn);
+    let args = instrument::InstrumentArgs::parse(args.into_iter().collect());
+    match args.and_then(|args| instrument::instrument(args, item)) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 /// Build static metrics.
 #[proc_macro]
 pub fn make_static_metric(input: TokenStream) -> TokenStream {
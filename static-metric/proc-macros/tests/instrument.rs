@@ -0,0 +1,93 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use lazy_static::lazy_static;
+use prometheus::{Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts};
+
+lazy_static! {
+    static ref DURATION: Histogram =
+        Histogram::with_opts(HistogramOpts::new("duration", "help")).unwrap();
+    static ref CALLS: IntCounter = IntCounter::new("calls", "help").unwrap();
+    static ref ERRORS: IntCounter = IntCounter::new("errors", "help").unwrap();
+    static ref DURATION_VEC: HistogramVec =
+        HistogramVec::new(HistogramOpts::new("duration_vec", "help"), &["function"]).unwrap();
+    static ref CALLS_VEC: IntCounterVec =
+        IntCounterVec::new(Opts::new("calls_vec", "help"), &["function"]).unwrap();
+    static ref ASYNC_ERRORS: IntCounter = IntCounter::new("async_errors", "help").unwrap();
+}
+
+#[prometheus::instrument(histogram = "DURATION", counter = "CALLS", errors = "ERRORS")]
+fn parse(s: &str) -> Result<u32, std::num::ParseIntError> {
+    if s.is_empty() {
+        return Ok(0);
+    }
+    let v = s.parse::<u32>()?;
+    Ok(v * 2)
+}
+
+#[prometheus::instrument(histogram = "DURATION_VEC", counter = "CALLS_VEC")]
+fn double(v: u32) -> u32 {
+    v * 2
+}
+
+struct Parser {
+    base: u32,
+}
+
+impl Parser {
+    #[prometheus::instrument(counter = "CALLS_VEC")]
+    fn offset(&self, v: u32) -> impl std::fmt::Display {
+        self.base + v
+    }
+
+    #[prometheus::instrument(counter = "CALLS_VEC")]
+    fn base_mut(&mut self) -> &mut u32 {
+        &mut self.base
+    }
+}
+
+#[prometheus::instrument(errors = "ASYNC_ERRORS", histogram = "DURATION_VEC")]
+async fn parse_async(s: String) -> Result<u32, std::num::ParseIntError> {
+    let v = async { s.parse::<u32>() }.await?;
+    Ok(v)
+}
+
+#[test]
+fn test_instrument() {
+    assert_eq!(parse(""), Ok(0));
+    assert_eq!(parse("21"), Ok(42));
+    assert!(parse("x").is_err());
+    assert_eq!(CALLS.get(), 3);
+    assert_eq!(ERRORS.get(), 1);
+    assert_eq!(DURATION.get_sample_count(), 3);
+
+    assert_eq!(double(2), 4);
+    assert_eq!(CALLS_VEC.with_label_values(&["double"]).get(), 1);
+    assert_eq!(
+        DURATION_VEC
+            .with_label_values(&["double"])
+            .get_sample_count(),
+        1
+    );
+
+    let parser = Parser { base: 10 };
+    assert_eq!(parser.offset(1).to_string(), "11");
+    assert_eq!(CALLS_VEC.with_label_values(&["offset"]).get(), 1);
+
+    let mut parser = parser;
+    *parser.base_mut() += 1;
+    assert_eq!(parser.base, 11);
+    assert_eq!(CALLS_VEC.with_label_values(&["base_mut"]).get(), 1);
+}
+
+#[tokio::test]
+async fn test_instrument_async() {
+    assert_eq!(parse_async("1".to_owned()).await, Ok(1));
+    assert!(parse_async("x".to_owned()).await.is_err());
+    assert_eq!(ASYNC_ERRORS.get(), 1);
+    assert_eq!(
+        DURATION_VEC
+            .with_label_values(&["parse_async"])
+            .get_sample_count(),
+        2
+    );
+}
@@ -30,6 +30,7 @@ use proc_macro_hack::proc_macro_hack;
 #[proc_macro_hack]
 pub use static_metric_proc_macros::auto_flush_from;
 
+pub use static_metric_proc_macros::Collector;
 pub use static_metric_proc_macros::{make_auto_flush_static_metric, make_static_metric};
 pub use static_metric_proc_macros::{
     register_static_counter_vec, register_static_gauge_vec, register_static_histogram_vec,
@@ -0,0 +1,66 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+extern crate prometheus;
+extern crate prometheus_static_metric;
+
+use prometheus::core::Collector;
+use prometheus::{Encoder, Histogram, IntCounter, IntCounterVec, IntGauge, Registry, TextEncoder};
+use prometheus_static_metric::Collector;
+
+#[derive(Clone, Collector)]
+#[collector(namespace = "test", subsystem = "pool")]
+struct PoolMetrics {
+    /// Number of idle
+    /// connections.
+    idle: IntGauge,
+    #[metric(name = "requests_total", help = "Number of requests.", labels("kind"))]
+    requests: IntCounterVec,
+    /// Time to acquire a connection.
+    #[metric(buckets(0.5, 1))]
+    acquire_seconds: Histogram,
+    #[metric(skip)]
+    #[allow(dead_code)]
+    max_size: usize,
+}
+
+#[derive(Clone, Collector)]
+struct ServerMetrics {
+    /// Number of restarts.
+    restarts: IntCounter,
+    pool: PoolMetrics,
+}
+
+#[test]
+fn test_derive_collector() {
+    let metrics = ServerMetrics::new().unwrap();
+    assert_eq!(metrics.pool.max_size, 0);
+    assert_eq!(metrics.desc().len(), 4);
+
+    metrics.restarts.inc();
+    metrics.pool.idle.set(3);
+    metrics.pool.requests.with_label_values(&["read"]).inc();
+    metrics.pool.acquire_seconds.observe(0.25);
+
+    let r = Registry::new();
+    r.register(Box::new(metrics.clone())).unwrap();
+    let mut writer = Vec::<u8>::new();
+    TextEncoder::new().encode(&r.gather(), &mut writer).unwrap();
+    let ans = r##"# HELP restarts Number of restarts.
+# TYPE restarts counter
+restarts 1
+# HELP test_pool_acquire_seconds Time to acquire a connection.
+# TYPE test_pool_acquire_seconds histogram
+test_pool_acquire_seconds_bucket{le="0.5"} 1
+test_pool_acquire_seconds_bucket{le="1"} 1
+test_pool_acquire_seconds_bucket{le="+Inf"} 1
+test_pool_acquire_seconds_sum 0.25
+test_pool_acquire_seconds_count 1
+# HELP test_pool_idle Number of idle connections.
+# TYPE test_pool_idle gauge
+test_pool_idle 3
+# HELP test_pool_requests_total Number of requests.
+# TYPE test_pool_requests_total counter
+test_pool_requests_total{kind="read"} 1
+"##;
+    assert_eq!(ans, String::from_utf8(writer).unwrap());
+}